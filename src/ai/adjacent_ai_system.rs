@@ -0,0 +1,73 @@
+use crate::raws::{faction_reaction, Reaction, RAWS};
+use crate::{Faction, Map, MyTurn, Position, WantsToMelee};
+use specs::prelude::*;
+
+/// Anything standing next to something its faction wants dead attacks it rather than doing
+/// anything else.
+pub struct AdjacentAI {}
+
+impl<'a> System<'a> for AdjacentAI {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        WriteStorage<'a, MyTurn>,
+        ReadStorage<'a, Faction>,
+        ReadStorage<'a, Position>,
+        ReadExpect<'a, Map>,
+        WriteStorage<'a, WantsToMelee>,
+        Entities<'a>,
+        ReadExpect<'a, Entity>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (mut turns, factions, positions, map, mut want_melee, entities, player) = data;
+
+        let mut turn_done: Vec<Entity> = Vec::new();
+        for (entity, _turn, my_faction, pos) in (&entities, &turns, &factions, &positions).join() {
+            if entity == *player {
+                continue;
+            }
+            let mut reactions: Vec<(Entity, Reaction)> = Vec::new();
+            for dy in -1..=1 {
+                for dx in -1..=1 {
+                    let (x, y) = (pos.x + dx, pos.y + dy);
+                    if (dx == 0 && dy == 0) || x < 0 || x >= map.width || y < 0 || y >= map.height {
+                        continue;
+                    }
+                    evaluate(
+                        map.xy_idx(x, y),
+                        &map,
+                        &factions,
+                        &my_faction.name,
+                        &mut reactions,
+                    );
+                }
+            }
+
+            if let Some((target, _)) = reactions.iter().find(|r| r.1 == Reaction::Attack) {
+                want_melee
+                    .insert(entity, WantsToMelee { target: *target })
+                    .expect("Unable to insert attack");
+                turn_done.push(entity);
+            }
+        }
+
+        for done in turn_done.iter() {
+            turns.remove(*done);
+        }
+    }
+}
+
+fn evaluate(
+    idx: usize,
+    map: &Map,
+    factions: &ReadStorage<Faction>,
+    my_faction: &str,
+    reactions: &mut Vec<(Entity, Reaction)>,
+) {
+    let raws = RAWS.lock().unwrap();
+    for other in map.tile_content[idx].iter() {
+        if let Some(faction) = factions.get(*other) {
+            reactions.push((*other, faction_reaction(my_faction, &faction.name, &raws)));
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use super::next_step;
+use crate::{EntityMoved, Map, MyTurn, Position, Viewshed, WantsToApproach};
+use specs::prelude::*;
+
+/// Takes one step along the shortest path towards whatever the entity wants to approach. If
+/// there's no way through, the turn is left for the rest of the pipeline.
+pub struct ApproachAI {}
+
+impl<'a> System<'a> for ApproachAI {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        WriteStorage<'a, MyTurn>,
+        WriteStorage<'a, WantsToApproach>,
+        WriteStorage<'a, Position>,
+        WriteExpect<'a, Map>,
+        WriteStorage<'a, Viewshed>,
+        WriteStorage<'a, EntityMoved>,
+        Entities<'a>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            mut turns,
+            mut want_approach,
+            mut positions,
+            mut map,
+            mut viewsheds,
+            mut entity_moved,
+            entities,
+        ) = data;
+
+        let mut turn_done: Vec<Entity> = Vec::new();
+        for (entity, pos, approach, viewshed, _myturn) in (
+            &entities,
+            &mut positions,
+            &want_approach,
+            &mut viewsheds,
+            &turns,
+        )
+            .join()
+        {
+            let mut idx = map.xy_idx(pos.x, pos.y);
+            if let Some(step) = next_step(&mut map, idx, approach.idx as usize) {
+                turn_done.push(entity);
+                map.blocked[idx] = false;
+                pos.x = step as i32 % map.width;
+                pos.y = step as i32 / map.width;
+                entity_moved
+                    .insert(entity, EntityMoved {})
+                    .expect("Unable to insert marker");
+                idx = map.xy_idx(pos.x, pos.y);
+                map.blocked[idx] = true;
+                viewshed.dirty = true;
+            }
+        }
+
+        want_approach.clear();
+
+        for done in turn_done.iter() {
+            turns.remove(*done);
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use super::next_step;
+use crate::{Awareness, Chasing, EntityMoved, Map, MyTurn, Position, Searching, Viewshed};
+use specs::prelude::*;
+
+/// Follows a target that has slipped out of view by heading for the last place it was seen.
+/// Once the chaser gets there (or can't find a way) the chase is over, and if it's the
+/// persistent type it starts searching the area that same turn.
+pub struct ChaseAI {}
+
+impl<'a> System<'a> for ChaseAI {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        WriteStorage<'a, MyTurn>,
+        WriteStorage<'a, Chasing>,
+        WriteStorage<'a, Position>,
+        WriteExpect<'a, Map>,
+        WriteStorage<'a, Viewshed>,
+        WriteStorage<'a, EntityMoved>,
+        Entities<'a>,
+        ReadStorage<'a, Awareness>,
+        WriteStorage<'a, Searching>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            mut turns,
+            mut chasing,
+            mut positions,
+            mut map,
+            mut viewsheds,
+            mut entity_moved,
+            entities,
+            awareness,
+            mut searching,
+        ) = data;
+
+        let mut turn_done: Vec<Entity> = Vec::new();
+        let mut end_chase: Vec<Entity> = Vec::new();
+        let mut start_search: Vec<(Entity, i32)> = Vec::new();
+        for (entity, pos, chase, viewshed, _myturn) in
+            (&entities, &mut positions, &chasing, &mut viewsheds, &turns).join()
+        {
+            if !entities.is_alive(chase.target) {
+                end_chase.push(entity);
+                continue;
+            }
+
+            let my_idx = map.xy_idx(pos.x, pos.y);
+            if let Some(step) = next_step(&mut map, my_idx, chase.last_seen as usize) {
+                turn_done.push(entity);
+                map.blocked[my_idx] = false;
+                pos.x = step as i32 % map.width;
+                pos.y = step as i32 / map.width;
+                entity_moved
+                    .insert(entity, EntityMoved {})
+                    .expect("Unable to insert marker");
+                let idx = map.xy_idx(pos.x, pos.y);
+                map.blocked[idx] = true;
+                viewshed.dirty = true;
+                if idx as i32 == chase.last_seen {
+                    end_chase.push(entity);
+                    start_search.push((entity, chase.last_seen));
+                }
+            } else {
+                end_chase.push(entity);
+                start_search.push((entity, chase.last_seen));
+            }
+        }
+
+        for done in end_chase.iter() {
+            chasing.remove(*done);
+        }
+        for (entity, center) in start_search.iter() {
+            if let Some(aware) = awareness.get(*entity) {
+                if aware.search_turns > 0 {
+                    searching
+                        .insert(
+                            *entity,
+                            Searching {
+                                center: *center,
+                                turns: aware.search_turns,
+                                destination: None,
+                            },
+                        )
+                        .expect("Unable to insert");
+                }
+            }
+        }
+        for done in turn_done.iter() {
+            turns.remove(*done);
+        }
+    }
+}
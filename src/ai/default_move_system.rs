@@ -0,0 +1,112 @@
+use crate::{tile_walkable, EntityMoved, Map, MoveMode, Movement, MyTurn, Position, Viewshed};
+use specs::prelude::*;
+
+/// What creatures do when nothing else has claimed their turn: stand still, shuffle about at
+/// random, or wander off towards a randomly chosen spot on the map.
+pub struct DefaultMoveAI {}
+
+impl<'a> System<'a> for DefaultMoveAI {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        WriteStorage<'a, MyTurn>,
+        WriteStorage<'a, MoveMode>,
+        WriteStorage<'a, Position>,
+        WriteExpect<'a, Map>,
+        WriteStorage<'a, Viewshed>,
+        WriteStorage<'a, EntityMoved>,
+        WriteExpect<'a, rltk::RandomNumberGenerator>,
+        Entities<'a>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            mut turns,
+            mut move_mode,
+            mut positions,
+            mut map,
+            mut viewsheds,
+            mut entity_moved,
+            mut rng,
+            entities,
+        ) = data;
+
+        let mut turn_done: Vec<Entity> = Vec::new();
+        for (entity, pos, mode, viewshed, _myturn) in (
+            &entities,
+            &mut positions,
+            &mut move_mode,
+            &mut viewsheds,
+            &turns,
+        )
+            .join()
+        {
+            turn_done.push(entity);
+            let my_idx = map.xy_idx(pos.x, pos.y);
+
+            let destination = match &mut mode.mode {
+                Movement::Static => None,
+
+                Movement::Random => {
+                    let mut x = pos.x;
+                    let mut y = pos.y;
+                    match rng.roll_dice(1, 5) {
+                        1 => x -= 1,
+                        2 => x += 1,
+                        3 => y -= 1,
+                        4 => y += 1,
+                        _ => {}
+                    }
+                    if x > 0 && x < map.width - 1 && y > 0 && y < map.height - 1 {
+                        Some(map.xy_idx(x, y))
+                    } else {
+                        None
+                    }
+                }
+
+                Movement::RandomWaypoint { path } => {
+                    if let Some(steps) = path {
+                        if steps.len() > 1 {
+                            let next = steps[1];
+                            if map.blocked[next] {
+                                // Someone's in the way; wait for them to move
+                                None
+                            } else {
+                                steps.remove(0);
+                                Some(next)
+                            }
+                        } else {
+                            *path = None;
+                            None
+                        }
+                    } else {
+                        let target_cell = rng.roll_dice(1, map.tiles.len() as i32 - 1) as usize;
+                        if tile_walkable(map.tiles[target_cell]) {
+                            let route = rltk::a_star_search(my_idx, target_cell, &*map);
+                            if route.success && route.steps.len() > 1 {
+                                *path = Some(route.steps);
+                            }
+                        }
+                        None
+                    }
+                }
+            };
+
+            if let Some(dest_idx) = destination {
+                if dest_idx != my_idx && !map.blocked[dest_idx] {
+                    map.blocked[my_idx] = false;
+                    map.blocked[dest_idx] = true;
+                    pos.x = dest_idx as i32 % map.width;
+                    pos.y = dest_idx as i32 / map.width;
+                    entity_moved
+                        .insert(entity, EntityMoved {})
+                        .expect("Unable to insert marker");
+                    viewshed.dirty = true;
+                }
+            }
+        }
+
+        for done in turn_done.iter() {
+            turns.remove(*done);
+        }
+    }
+}
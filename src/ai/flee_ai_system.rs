@@ -0,0 +1,71 @@
+use crate::{EntityMoved, Map, MyTurn, Position, Viewshed, WantsToFlee};
+use specs::prelude::*;
+
+/// Runs away from everything the entity is afraid of, using a Dijkstra map seeded with the
+/// threats' positions and stepping to whichever neighbouring tile is furthest from them.
+pub struct FleeAI {}
+
+impl<'a> System<'a> for FleeAI {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        WriteStorage<'a, MyTurn>,
+        WriteStorage<'a, WantsToFlee>,
+        WriteStorage<'a, Position>,
+        WriteExpect<'a, Map>,
+        WriteStorage<'a, Viewshed>,
+        WriteStorage<'a, EntityMoved>,
+        Entities<'a>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            mut turns,
+            mut want_flee,
+            mut positions,
+            mut map,
+            mut viewsheds,
+            mut entity_moved,
+            entities,
+        ) = data;
+
+        let mut turn_done: Vec<Entity> = Vec::new();
+        for (entity, pos, flee, viewshed, _myturn) in (
+            &entities,
+            &mut positions,
+            &want_flee,
+            &mut viewsheds,
+            &turns,
+        )
+            .join()
+        {
+            turn_done.push(entity);
+            let my_idx = map.xy_idx(pos.x, pos.y);
+            let flee_map = rltk::DijkstraMap::new(
+                map.width as usize,
+                map.height as usize,
+                &flee.indices,
+                &*map,
+                100.0,
+            );
+            let flee_target = rltk::DijkstraMap::find_highest_exit(&flee_map, my_idx, &*map);
+            if let Some(flee_target) = flee_target {
+                if !map.blocked[flee_target] {
+                    map.blocked[my_idx] = false;
+                    map.blocked[flee_target] = true;
+                    viewshed.dirty = true;
+                    pos.x = flee_target as i32 % map.width;
+                    pos.y = flee_target as i32 / map.width;
+                    entity_moved
+                        .insert(entity, EntityMoved {})
+                        .expect("Unable to insert marker");
+                }
+            }
+        }
+
+        want_flee.clear();
+
+        for done in turn_done.iter() {
+            turns.remove(*done);
+        }
+    }
+}
@@ -0,0 +1,41 @@
+//! Monster AI, split into small systems that each get a chance at a creature's turn. Each one
+//! that acts removes the `MyTurn` marker, so later systems in the pipeline leave it alone:
+//! ambient chatter and status checks come first, then attacking whatever is adjacent, deciding
+//! what to do about what it can see, approaching or fleeing, chasing and then searching for a
+//! lost target, and finally idle movement.
+
+mod adjacent_ai_system;
+mod approach_ai_system;
+mod chase_ai_system;
+mod default_move_system;
+mod flee_ai_system;
+mod quipping;
+mod search_ai_system;
+mod turn_status;
+mod visible_ai_system;
+pub use adjacent_ai_system::AdjacentAI;
+pub use approach_ai_system::ApproachAI;
+pub use chase_ai_system::ChaseAI;
+pub use default_move_system::DefaultMoveAI;
+pub use flee_ai_system::FleeAI;
+pub use quipping::QuipSystem;
+pub use search_ai_system::SearchAI;
+pub use turn_status::TurnStatusSystem;
+pub use visible_ai_system::VisibleAI;
+
+use crate::Map;
+
+/// The first step along the shortest path from `start` towards `goal`, if there is one. The
+/// goal itself is treated as open while searching, since creatures block their own tiles, but
+/// the step returned is never onto a blocked tile.
+fn next_step(map: &mut Map, start: usize, goal: usize) -> Option<usize> {
+    let goal_blocked = map.blocked[goal];
+    map.blocked[goal] = false;
+    let path = rltk::a_star_search(start, goal, &*map);
+    map.blocked[goal] = goal_blocked;
+    if path.success && path.steps.len() > 1 && !map.blocked[path.steps[1]] {
+        Some(path.steps[1])
+    } else {
+        None
+    }
+}
@@ -0,0 +1,41 @@
+use crate::{gamelog::GameLog, Map, MyTurn, Name, Position, Quips, Viewshed};
+use rltk::Point;
+use specs::prelude::*;
+
+/// Now and then, NPCs who can see the player (and be seen by them) say something. Talking
+/// doesn't cost them their turn.
+pub struct QuipSystem {}
+
+impl<'a> System<'a> for QuipSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        WriteExpect<'a, GameLog>,
+        ReadStorage<'a, Quips>,
+        ReadStorage<'a, Name>,
+        ReadStorage<'a, MyTurn>,
+        ReadExpect<'a, Point>,
+        ReadStorage<'a, Viewshed>,
+        ReadStorage<'a, Position>,
+        ReadExpect<'a, Map>,
+        WriteExpect<'a, rltk::RandomNumberGenerator>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (mut log, quips, names, turns, player_pos, viewsheds, positions, map, mut rng) = data;
+
+        for (quip, name, viewshed, pos, _turn) in
+            (&quips, &names, &viewsheds, &positions, &turns).join()
+        {
+            let idx = map.xy_idx(pos.x, pos.y);
+            if !quip.available.is_empty()
+                && map.visible_tiles[idx]
+                && viewshed.visible_tiles.contains(&*player_pos)
+                && rng.roll_dice(1, 6) == 1
+            {
+                let line =
+                    &quip.available[rng.roll_dice(1, quip.available.len() as i32) as usize - 1];
+                log.entries.push(format!("{} says \"{}\"", name.name, line));
+            }
+        }
+    }
+}
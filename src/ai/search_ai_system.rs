@@ -0,0 +1,99 @@
+use crate::{tile_walkable, EntityMoved, Map, MyTurn, Position, Searching, Viewshed};
+use specs::prelude::*;
+
+const SEARCH_RADIUS: i32 = 4;
+
+/// Wanders around the spot where a chase went cold, picking random nearby tiles to check,
+/// until the entity runs out of patience. Seeing an enemy again hands things back to the
+/// visible AI, which cancels the search.
+pub struct SearchAI {}
+
+impl<'a> System<'a> for SearchAI {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        WriteStorage<'a, MyTurn>,
+        WriteStorage<'a, Searching>,
+        WriteStorage<'a, Position>,
+        WriteExpect<'a, Map>,
+        WriteStorage<'a, Viewshed>,
+        WriteStorage<'a, EntityMoved>,
+        WriteExpect<'a, rltk::RandomNumberGenerator>,
+        Entities<'a>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            mut turns,
+            mut searching,
+            mut positions,
+            mut map,
+            mut viewsheds,
+            mut entity_moved,
+            mut rng,
+            entities,
+        ) = data;
+
+        let mut turn_done: Vec<Entity> = Vec::new();
+        let mut gave_up: Vec<Entity> = Vec::new();
+        for (entity, pos, search, viewshed, _myturn) in (
+            &entities,
+            &mut positions,
+            &mut searching,
+            &mut viewsheds,
+            &turns,
+        )
+            .join()
+        {
+            search.turns -= 1;
+            if search.turns < 0 {
+                // Lost them; go back to whatever we were doing
+                gave_up.push(entity);
+                continue;
+            }
+            turn_done.push(entity);
+
+            let my_idx = map.xy_idx(pos.x, pos.y) as i32;
+            if search.destination.is_none_or(|d| d == my_idx) {
+                search.destination = None;
+                let (cx, cy) = (search.center % map.width, search.center / map.width);
+                for _ in 0..10 {
+                    let x = cx + rng.roll_dice(1, SEARCH_RADIUS * 2 + 1) - (SEARCH_RADIUS + 1);
+                    let y = cy + rng.roll_dice(1, SEARCH_RADIUS * 2 + 1) - (SEARCH_RADIUS + 1);
+                    if x < 1 || x > map.width - 2 || y < 1 || y > map.height - 2 {
+                        continue;
+                    }
+                    let idx = map.xy_idx(x, y);
+                    if tile_walkable(map.tiles[idx]) && idx as i32 != my_idx {
+                        search.destination = Some(idx as i32);
+                        break;
+                    }
+                }
+            }
+
+            if let Some(destination) = search.destination {
+                let path = rltk::a_star_search(my_idx as usize, destination as usize, &*map);
+                if path.success && path.steps.len() > 1 {
+                    map.blocked[my_idx as usize] = false;
+                    pos.x = path.steps[1] as i32 % map.width;
+                    pos.y = path.steps[1] as i32 / map.width;
+                    entity_moved
+                        .insert(entity, EntityMoved {})
+                        .expect("Unable to insert marker");
+                    let idx = map.xy_idx(pos.x, pos.y);
+                    map.blocked[idx] = true;
+                    viewshed.dirty = true;
+                } else {
+                    // Can't get there; pick somewhere else next turn
+                    search.destination = None;
+                }
+            }
+        }
+
+        for done in gave_up.iter() {
+            searching.remove(*done);
+        }
+        for done in turn_done.iter() {
+            turns.remove(*done);
+        }
+    }
+}
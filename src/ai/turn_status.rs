@@ -0,0 +1,47 @@
+use crate::{particle_system::ParticleBuilder, MyTurn, Position, StatusEffects, StatusKind};
+use specs::prelude::*;
+
+/// Confused and stunned creatures lose their turn; the status system counts them down. The
+/// player's own statuses are handled when reading input.
+pub struct TurnStatusSystem {}
+
+impl<'a> System<'a> for TurnStatusSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        WriteStorage<'a, MyTurn>,
+        ReadStorage<'a, StatusEffects>,
+        ReadStorage<'a, Position>,
+        Entities<'a>,
+        ReadExpect<'a, Entity>,
+        WriteExpect<'a, ParticleBuilder>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (mut turns, status_effects, positions, entities, player_entity, mut particle_builder) =
+            data;
+
+        let mut not_my_turn: Vec<Entity> = Vec::new();
+        for (entity, _turn, statuses, pos) in
+            (&entities, &turns, &status_effects, &positions).join()
+        {
+            if entity == *player_entity {
+                continue;
+            }
+            if statuses.has(StatusKind::Confused) || statuses.has(StatusKind::Stunned) {
+                not_my_turn.push(entity);
+                particle_builder.request(
+                    pos.x,
+                    pos.y,
+                    rltk::RGB::named(rltk::MAGENTA),
+                    rltk::RGB::named(rltk::BLACK),
+                    rltk::to_cp437('?'),
+                    200.0,
+                );
+            }
+        }
+
+        for e in not_my_turn {
+            turns.remove(e);
+        }
+    }
+}
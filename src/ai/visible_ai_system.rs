@@ -0,0 +1,187 @@
+use super::next_step;
+use crate::raws::{faction_reaction, Reaction, RAWS};
+use crate::{
+    gamelog::GameLog, particle_system::ParticleBuilder, Awareness, Chasing, Faction, Map, MyTurn,
+    Name, Position, Searching, Viewshed, WantsToApproach, WantsToFlee,
+};
+use rltk::Point;
+use specs::prelude::*;
+
+/// Decides what creatures do about everything they can see. Anything frightening makes them
+/// run; otherwise they close in on the nearest enemy they can reach and remember where it was
+/// in case they lose sight of it. Spotting a new enemy can raise the alarm, setting nearby
+/// allies on its trail too.
+pub struct VisibleAI {}
+
+impl<'a> System<'a> for VisibleAI {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        ReadStorage<'a, MyTurn>,
+        ReadStorage<'a, Faction>,
+        ReadStorage<'a, Position>,
+        WriteExpect<'a, Map>,
+        WriteStorage<'a, WantsToApproach>,
+        WriteStorage<'a, WantsToFlee>,
+        WriteStorage<'a, Chasing>,
+        Entities<'a>,
+        ReadExpect<'a, Entity>,
+        ReadStorage<'a, Viewshed>,
+        ReadStorage<'a, Awareness>,
+        WriteStorage<'a, Searching>,
+        ReadStorage<'a, Name>,
+        WriteExpect<'a, GameLog>,
+        WriteExpect<'a, ParticleBuilder>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            turns,
+            factions,
+            positions,
+            mut map,
+            mut want_approach,
+            mut want_flee,
+            mut chasing,
+            entities,
+            player,
+            viewsheds,
+            awareness,
+            mut searching,
+            names,
+            mut log,
+            mut particle_builder,
+        ) = data;
+
+        let mut alerts: Vec<(Entity, Entity, i32)> = Vec::new();
+
+        for (entity, _turn, my_faction, pos, viewshed) in
+            (&entities, &turns, &factions, &positions, &viewsheds).join()
+        {
+            if entity == *player {
+                continue;
+            }
+            let my_idx = map.xy_idx(pos.x, pos.y);
+            let mut reactions: Vec<(usize, Reaction, Entity)> = Vec::new();
+            let mut flee: Vec<usize> = Vec::new();
+            for visible_tile in viewshed.visible_tiles.iter() {
+                let idx = map.xy_idx(visible_tile.x, visible_tile.y);
+                if my_idx != idx {
+                    evaluate(idx, &map, &factions, &my_faction.name, &mut reactions);
+                }
+            }
+
+            let my_point = Point::new(pos.x, pos.y);
+            let mut targets: Vec<(f32, usize, Entity)> = Vec::new();
+            for (idx, reaction, other) in reactions.iter() {
+                match reaction {
+                    Reaction::Attack => {
+                        let target_point =
+                            Point::new(*idx as i32 % map.width, *idx as i32 / map.width);
+                        let distance =
+                            rltk::DistanceAlg::Pythagoras.distance2d(my_point, target_point);
+                        targets.push((distance, *idx, *other));
+                    }
+                    Reaction::Flee => flee.push(*idx),
+                    Reaction::Ignore => {}
+                }
+            }
+
+            // Go for the nearest enemy there's a way to; one we can't reach is no use
+            targets.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+            let nearest = targets.into_iter().find(|(distance, idx, _)| {
+                *distance < 1.5 || next_step(&mut map, my_idx, *idx).is_some()
+            });
+
+            if !flee.is_empty() {
+                want_flee
+                    .insert(entity, WantsToFlee { indices: flee })
+                    .expect("Unable to insert");
+            } else if let Some((_, idx, target)) = nearest {
+                searching.remove(entity);
+                let newly_spotted = chasing.get(entity).is_none_or(|c| c.target != target);
+                if newly_spotted {
+                    if let Some(radius) = awareness.get(entity).and_then(|a| a.alert_radius) {
+                        alerts.push((entity, target, radius));
+                        if map.visible_tiles[my_idx] {
+                            if let Some(name) = names.get(entity) {
+                                log.entries.push(format!("{} shouts an alarm!", &name.name));
+                            }
+                        }
+                        particle_builder.request(
+                            pos.x,
+                            pos.y,
+                            rltk::RGB::named(rltk::YELLOW),
+                            rltk::RGB::named(rltk::BLACK),
+                            rltk::to_cp437('!'),
+                            200.0,
+                        );
+                    }
+                }
+                want_approach
+                    .insert(entity, WantsToApproach { idx: idx as i32 })
+                    .expect("Unable to insert");
+                chasing
+                    .insert(
+                        entity,
+                        Chasing {
+                            target,
+                            last_seen: idx as i32,
+                        },
+                    )
+                    .expect("Unable to insert");
+            }
+        }
+
+        // Allies within earshot come running to where the enemy is now
+        for (caller, target, radius) in alerts.iter() {
+            let (Some(caller_faction), Some(caller_pos), Some(target_pos)) = (
+                factions.get(*caller),
+                positions.get(*caller),
+                positions.get(*target),
+            ) else {
+                continue;
+            };
+            let caller_point = Point::new(caller_pos.x, caller_pos.y);
+            let last_seen = map.xy_idx(target_pos.x, target_pos.y) as i32;
+            for (ally, faction, pos) in (&entities, &factions, &positions).join() {
+                if ally == *caller || ally == *player || faction.name != caller_faction.name {
+                    continue;
+                }
+                let distance = rltk::DistanceAlg::Pythagoras
+                    .distance2d(caller_point, Point::new(pos.x, pos.y));
+                if distance > *radius as f32 || chasing.get(ally).is_some() {
+                    continue;
+                }
+                searching.remove(ally);
+                chasing
+                    .insert(
+                        ally,
+                        Chasing {
+                            target: *target,
+                            last_seen,
+                        },
+                    )
+                    .expect("Unable to insert");
+            }
+        }
+    }
+}
+
+fn evaluate(
+    idx: usize,
+    map: &Map,
+    factions: &ReadStorage<Faction>,
+    my_faction: &str,
+    reactions: &mut Vec<(usize, Reaction, Entity)>,
+) {
+    let raws = RAWS.lock().unwrap();
+    for other in map.tile_content[idx].iter() {
+        if let Some(faction) = factions.get(*other) {
+            reactions.push((
+                idx,
+                faction_reaction(my_faction, &faction.name, &raws),
+                *other,
+            ));
+        }
+    }
+}
@@ -4,6 +4,7 @@ use specs::error::NoError;
 use specs::prelude::*;
 use specs::saveload::{ConvertSaveload, Marker};
 use specs_derive::*;
+use std::collections::HashMap;
 
 #[derive(Component, ConvertSaveload, Clone)]
 pub struct Position {
@@ -29,9 +30,6 @@ pub struct Viewshed {
     pub dirty: bool,
 }
 
-#[derive(Component, Debug, Serialize, Deserialize, Clone)]
-pub struct Monster {}
-
 #[derive(Component, Debug, ConvertSaveload, Clone)]
 pub struct Name {
     pub name: String,
@@ -40,39 +38,76 @@ pub struct Name {
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
 pub struct BlocksTile {}
 
+#[derive(Serialize, Deserialize, Copy, Clone, Debug)]
+pub struct Attribute {
+    pub base: i32,
+    pub modifiers: i32,
+    pub bonus: i32,
+}
+
+impl Attribute {
+    pub fn new(base: i32) -> Attribute {
+        Attribute {
+            base,
+            modifiers: 0,
+            bonus: crate::gamesystem::attr_bonus(base),
+        }
+    }
+}
+
 #[derive(Component, Debug, ConvertSaveload, Clone)]
-pub struct CombatStats {
-    pub max_hp: i32,
-    pub hp: i32,
-    pub defense: i32,
-    pub power: i32,
+pub struct Attributes {
+    pub might: Attribute,
+    pub fitness: Attribute,
+    pub quickness: Attribute,
+    pub intelligence: Attribute,
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Skill {
+    Melee,
+    Defense,
+    Magic,
+    Ranged,
 }
 
 #[derive(Component, Debug, ConvertSaveload, Clone)]
-pub struct WantsToMelee {
-    pub target: Entity,
+pub struct Skills {
+    pub skills: HashMap<Skill, i32>,
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug)]
+pub struct Pool {
+    pub max: i32,
+    pub current: i32,
 }
 
 #[derive(Component, Debug, ConvertSaveload, Clone)]
-pub struct SufferDamage {
-    pub amount: Vec<i32>,
+pub struct Pools {
+    pub hit_points: Pool,
+    pub xp: i32,
+    pub level: i32,
+    pub gold: f32,
 }
 
-impl SufferDamage {
-    pub fn new_damage(store: &mut WriteStorage<SufferDamage>, victim: Entity, amount: i32) {
-        if let Some(suffering) = store.get_mut(victim) {
-            suffering.amount.push(amount);
-        } else {
-            let dmg = SufferDamage {
-                amount: vec![amount],
-            };
-            store.insert(victim, dmg).expect("Unable to insert damage");
-        }
-    }
+/// Counts down to an entity's next turn. When it runs out the entity gets a `MyTurn`.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Initiative {
+    pub current: i32,
+}
+
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct MyTurn {}
+
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct WantsToMelee {
+    pub target: Entity,
 }
 
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
-pub struct Item {}
+pub struct Item {
+    pub base_value: f32,
+}
 
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
 pub struct Consumable {}
@@ -92,11 +127,107 @@ pub struct AreaOfEffect {
     pub radius: i32,
 }
 
-#[derive(Component, Debug, ConvertSaveload, Clone)]
-pub struct Confusion {
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Serialize, Deserialize, Debug)]
+pub enum StatusKind {
+    Poisoned,
+    Slowed,
+    Hasted,
+    Stunned,
+    Blinded,
+    Regenerating,
+    Confused,
+}
+
+impl StatusKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            StatusKind::Poisoned => "Poisoned",
+            StatusKind::Slowed => "Slowed",
+            StatusKind::Hasted => "Hasted",
+            StatusKind::Stunned => "Stunned",
+            StatusKind::Blinded => "Blinded",
+            StatusKind::Regenerating => "Regenerating",
+            StatusKind::Confused => "Confused",
+        }
+    }
+
+    /// Poison and regeneration pile up; everything else just lasts longer.
+    pub fn stacks(&self) -> bool {
+        matches!(self, StatusKind::Poisoned | StatusKind::Regenerating)
+    }
+}
+
+/// One running status. `amount` is the damage or healing still to be dealt out by poison and
+/// regeneration; other statuses leave it at zero.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Status {
+    pub kind: StatusKind,
+    pub turns: i32,
+    pub amount: i32,
+}
+
+#[derive(Component, Serialize, Deserialize, Clone, Debug, Default)]
+pub struct StatusEffects {
+    pub effects: Vec<Status>,
+}
+
+impl StatusEffects {
+    pub fn has(&self, kind: StatusKind) -> bool {
+        self.effects.iter().any(|s| s.kind == kind)
+    }
+
+    /// Adds a status to a victim. Stacking statuses are added alongside any already running;
+    /// the others refresh to whichever duration is longer. Haste and slow cancel each other out.
+    pub fn add_status(store: &mut WriteStorage<StatusEffects>, victim: Entity, status: Status) {
+        if store.get(victim).is_none() {
+            store
+                .insert(victim, StatusEffects::default())
+                .expect("Unable to insert status effects");
+        }
+        let effects = &mut store.get_mut(victim).unwrap().effects;
+
+        let opposite = match status.kind {
+            StatusKind::Hasted => Some(StatusKind::Slowed),
+            StatusKind::Slowed => Some(StatusKind::Hasted),
+            _ => None,
+        };
+        if let Some(opposite) = opposite {
+            if effects.iter().any(|s| s.kind == opposite) {
+                effects.retain(|s| s.kind != opposite);
+                return;
+            }
+        }
+
+        if !status.kind.stacks() {
+            if let Some(existing) = effects.iter_mut().find(|s| s.kind == status.kind) {
+                existing.turns = i32::max(existing.turns, status.turns);
+                return;
+            }
+        }
+        effects.push(status);
+    }
+}
+
+/// A status an item inflicts. Poison and regeneration roll their dice when applied to get the
+/// total damage or healing dealt over `turns`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct StatusSpec {
+    pub kind: StatusKind,
     pub turns: i32,
+    pub n_dice: i32,
+    pub die_type: i32,
+    pub bonus: i32,
 }
 
+#[derive(Component, Serialize, Deserialize, Clone, Debug)]
+pub struct InflictsStatus {
+    pub statuses: Vec<StatusSpec>,
+}
+
+/// Teleports the user to the targeted tile.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Blink {}
+
 #[derive(Component, Debug, ConvertSaveload, Clone)]
 pub struct ProvidesHealing {
     pub heal_amount: i32,
@@ -141,6 +272,13 @@ pub struct WantsToRemoveItem {
 pub enum EquipmentSlot {
     Melee,
     Shield,
+    Head,
+    Torso,
+    Legs,
+    Feet,
+    Hands,
+    Ring,
+    Amulet,
 }
 
 #[derive(Component, Serialize, Deserialize, Clone)]
@@ -154,14 +292,68 @@ pub struct Equipped {
     pub slot: EquipmentSlot,
 }
 
-#[derive(Component, ConvertSaveload, Clone)]
-pub struct MeleePowerBonus {
-    pub power: i32,
+#[derive(PartialEq, Copy, Clone, Serialize, Deserialize, Debug)]
+pub enum WeaponAttribute {
+    Might,
+    Quickness,
+}
+
+/// How a weapon hits: which attribute it scales with, its damage dice and its to-hit bonus.
+#[derive(Component, ConvertSaveload, Clone, Debug)]
+pub struct MeleeWeapon {
+    pub attribute: WeaponAttribute,
+    pub damage_n_dice: i32,
+    pub damage_die_type: i32,
+    pub damage_bonus: i32,
+    pub hit_bonus: i32,
 }
 
+/// A bow, crossbow or thrown weapon. Bows and crossbows use up matching `Ammunition` from the
+/// wielder's backpack; thrown weapons (`ammo` of `None`) are themselves thrown at the target.
+#[derive(Component, Serialize, Deserialize, Clone, Debug)]
+pub struct RangedWeapon {
+    pub attribute: WeaponAttribute,
+    pub damage_n_dice: i32,
+    pub damage_die_type: i32,
+    pub damage_bonus: i32,
+    pub hit_bonus: i32,
+    pub range: i32,
+    pub ammo: Option<String>,
+}
+
+#[derive(Component, Serialize, Deserialize, Clone, Debug)]
+pub struct Ammunition {
+    pub kind: String,
+    pub count: i32,
+}
+
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct WantsToShoot {
+    pub target: Entity,
+}
+
+/// A single claw, bite or sting a creature can attack with instead of a weapon.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct NaturalAttack {
+    pub name: String,
+    pub damage_n_dice: i32,
+    pub damage_die_type: i32,
+    pub damage_bonus: i32,
+    pub hit_bonus: i32,
+}
+
+/// Hide, scales and natural weapons. `armor_class` replaces the usual base AC of 10.
+#[derive(Component, Serialize, Deserialize, Clone, Debug)]
+pub struct NaturalAttackDefense {
+    pub armor_class: Option<i32>,
+    pub attacks: Vec<NaturalAttack>,
+}
+
+/// Armor worn in any equipment slot; the armor class of everything worn is added up.
 #[derive(Component, ConvertSaveload, Clone)]
-pub struct DefenseBonus {
-    pub defense: i32,
+pub struct Wearable {
+    pub armor_class: f32,
+    pub weight_lbs: f32,
 }
 
 #[derive(Component, Serialize, Deserialize, Clone)]
@@ -201,8 +393,84 @@ pub struct EntityMoved {}
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
 pub struct SingleActivation {}
 
+/// Trades in items from the listed vendor categories (see `vendor_category` in the raws).
 #[derive(Component, Debug, Serialize, Deserialize, Clone)]
-pub struct Bystander {}
+pub struct Vendor {
+    pub categories: Vec<String>,
+}
+
+/// Things an NPC mutters when the player is in view.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Quips {
+    pub available: Vec<String>,
+}
+
+/// Names the raws dialogue tree used when the player talks to this NPC.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Dialogue {
+    pub tree: String,
+}
+
+/// Which side an entity is on. How factions treat one another lives in `raws/factions.json`.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Faction {
+    pub name: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub enum Movement {
+    Static,
+    Random,
+    RandomWaypoint { path: Option<Vec<usize>> },
+}
+
+/// How an entity gets about when it has nothing better to do.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct MoveMode {
+    pub mode: Movement,
+}
+
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct WantsToApproach {
+    pub idx: i32,
+}
+
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct WantsToFlee {
+    pub indices: Vec<usize>,
+}
+
+/// Keeps a monster after its quarry once it drops out of sight, heading for the tile where it
+/// was last seen.
+#[derive(Component, Debug, ConvertSaveload, Clone)]
+pub struct Chasing {
+    pub target: Entity,
+    pub last_seen: i32,
+}
+
+/// Set when a chase goes cold: the entity pokes around near `center` for a few more turns,
+/// heading for `destination`, before giving up.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Searching {
+    pub center: i32,
+    pub turns: i32,
+    pub destination: Option<i32>,
+}
+
+/// How long a creature keeps looking for a quarry it lost sight of, and how far away its allies
+/// can hear it raise the alarm when it spots one.
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct Awareness {
+    pub search_turns: i32,
+    pub alert_radius: Option<i32>,
+}
+
+#[derive(Component, Debug, Serialize, Deserialize, Clone)]
+pub struct OtherLevelPosition {
+    pub x: i32,
+    pub y: i32,
+    pub depth: i32,
+}
 
 // Serialization helper code. We need to implement ConvertSaveLoad for each type that contains an
 // Entity.
@@ -214,3 +482,8 @@ pub struct SerializeMe;
 pub struct SerializationHelper {
     pub map: super::map::Map,
 }
+
+#[derive(Component, Serialize, Deserialize, Clone)]
+pub struct DMSerializationHelper {
+    pub map: super::map::MasterDungeonMap,
+}
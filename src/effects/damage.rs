@@ -0,0 +1,111 @@
+use super::{add_effect, entity_position, EffectSpawner, EffectType, Targets};
+use crate::components::{Attributes, Player, Pools, Skills};
+use crate::gamelog::GameLog;
+use crate::gamesystem::{attr_bonus, npc_hp, player_hp_at_level, xp_for_kill, xp_to_next_level};
+use crate::map::Map;
+use specs::prelude::*;
+
+pub fn inflict_damage(ecs: &mut World, damage: &EffectSpawner, target: Entity) {
+    let mut killed_level = None;
+    {
+        let mut pools = ecs.write_storage::<Pools>();
+        if let Some(pool) = pools.get_mut(target) {
+            if let EffectType::Damage { amount } = damage.effect_type {
+                let was_alive = pool.hit_points.current > 0;
+                pool.hit_points.current -= amount;
+                if was_alive && pool.hit_points.current < 1 {
+                    killed_level = Some(pool.level);
+                }
+            }
+        } else {
+            return;
+        }
+    }
+
+    if let Some(idx) = entity_position(ecs, target) {
+        ecs.write_resource::<Map>().bloodstains.insert(idx as usize);
+    }
+
+    // Whoever lands the killing blow gets the experience
+    if let (Some(level), Some(attacker)) = (killed_level, damage.creator) {
+        award_xp(ecs, attacker, xp_for_kill(level));
+    }
+}
+
+pub fn heal_damage(ecs: &mut World, heal: &EffectSpawner, target: Entity) {
+    let mut pools = ecs.write_storage::<Pools>();
+    if let Some(pool) = pools.get_mut(target) {
+        if let EffectType::Healing { amount } = heal.effect_type {
+            pool.hit_points.current =
+                i32::min(pool.hit_points.max, pool.hit_points.current + amount);
+        }
+    }
+}
+
+fn award_xp(ecs: &mut World, attacker: Entity, xp: i32) {
+    let mut pools = ecs.write_storage::<Pools>();
+    let mut attributes = ecs.write_storage::<Attributes>();
+    let mut skills = ecs.write_storage::<Skills>();
+    let players = ecs.read_storage::<Player>();
+    let mut log = ecs.write_resource::<GameLog>();
+    let mut rng = ecs.write_resource::<rltk::RandomNumberGenerator>();
+
+    if let Some(pools) = pools.get_mut(attacker) {
+        pools.xp += xp;
+        while pools.xp >= xp_to_next_level(pools.level) {
+            pools.level += 1;
+
+            // Improve a random attribute
+            if let Some(attr) = attributes.get_mut(attacker) {
+                let boosted = match rng.roll_dice(1, 4) {
+                    1 => &mut attr.might,
+                    2 => &mut attr.fitness,
+                    3 => &mut attr.quickness,
+                    _ => &mut attr.intelligence,
+                };
+                boosted.base += 1;
+                boosted.bonus = attr_bonus(boosted.base + boosted.modifiers);
+
+                // Toughen up
+                let fitness = attr.fitness.base + attr.fitness.modifiers;
+                pools.hit_points.max = if players.get(attacker).is_some() {
+                    player_hp_at_level(fitness, pools.level)
+                } else {
+                    npc_hp(fitness, pools.level)
+                };
+                pools.hit_points.current = pools.hit_points.max;
+            }
+
+            // Improve all skills
+            if let Some(skills) = skills.get_mut(attacker) {
+                for skill in skills.skills.iter_mut() {
+                    *skill.1 += 1;
+                }
+            }
+
+            if players.get(attacker).is_some() {
+                log.entries.push(format!(
+                    "Congratulations, you are now level {}!",
+                    pools.level
+                ));
+                if let Some(idx) = entity_position(ecs, attacker) {
+                    let width = ecs.fetch::<Map>().width;
+                    let tiles: Vec<i32> = (1..4)
+                        .map(|i| idx - width * i)
+                        .filter(|tile| *tile >= width)
+                        .collect();
+                    add_effect(
+                        None,
+                        EffectType::Particle {
+                            glyph: rltk::to_cp437('↑'),
+                            fg: rltk::RGB::named(rltk::GOLD),
+                            bg: rltk::RGB::named(rltk::BLACK),
+                            lifespan: 400.0,
+                        },
+                        Targets::Tiles { tiles },
+                    );
+                }
+            }
+        }
+    }
+}
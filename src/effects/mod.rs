@@ -0,0 +1,152 @@
+use crate::map::Map;
+use specs::prelude::*;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+mod damage;
+mod hunger;
+mod movement;
+mod particles;
+mod status;
+mod targeting;
+mod triggers;
+pub use targeting::*;
+
+lazy_static! {
+    pub static ref EFFECT_QUEUE: Mutex<VecDeque<EffectSpawner>> = Mutex::new(VecDeque::new());
+}
+
+/// Something that happens to an entity or a patch of map.
+pub enum EffectType {
+    Damage {
+        amount: i32,
+    },
+    Healing {
+        amount: i32,
+    },
+    Status {
+        kind: crate::StatusKind,
+        turns: i32,
+        amount: i32,
+    },
+    WellFed,
+    Particle {
+        glyph: rltk::FontCharType,
+        fg: rltk::RGB,
+        bg: rltk::RGB,
+        lifespan: f32,
+    },
+    TeleportTo {
+        x: i32,
+        y: i32,
+    },
+    ItemUse {
+        item: Entity,
+    },
+    TriggerFire {
+        trigger: Entity,
+    },
+}
+
+/// Who or where an effect lands. Tile indices are map indices.
+#[derive(Clone)]
+pub enum Targets {
+    Single { target: Entity },
+    Tile { tile_idx: i32 },
+    Tiles { tiles: Vec<i32> },
+    Area { center: i32, radius: i32 },
+}
+
+pub struct EffectSpawner {
+    pub creator: Option<Entity>,
+    pub effect_type: EffectType,
+    pub targets: Targets,
+}
+
+/// Queues an effect. `creator` is whoever caused it, and gets the credit for any kills.
+pub fn add_effect(creator: Option<Entity>, effect_type: EffectType, targets: Targets) {
+    EFFECT_QUEUE.lock().unwrap().push_back(EffectSpawner {
+        creator,
+        effect_type,
+        targets,
+    });
+}
+
+/// Throws away anything still queued. Call this whenever the world is replaced, since queued
+/// effects refer to its entities.
+pub fn clear_effects() {
+    EFFECT_QUEUE.lock().unwrap().clear();
+}
+
+/// Applies everything in the queue, including any effects queued while doing so.
+pub fn run_effects_queue(ecs: &mut World) {
+    loop {
+        let effect: Option<EffectSpawner> = EFFECT_QUEUE.lock().unwrap().pop_front();
+        if let Some(effect) = effect {
+            target_applicator(ecs, &effect);
+        } else {
+            break;
+        }
+    }
+}
+
+fn target_applicator(ecs: &mut World, effect: &EffectSpawner) {
+    match &effect.effect_type {
+        EffectType::ItemUse { item } => {
+            triggers::item_trigger(effect.creator, *item, &effect.targets, ecs)
+        }
+        EffectType::TriggerFire { trigger } => {
+            triggers::trigger(effect.creator, *trigger, &effect.targets, ecs)
+        }
+        _ => {
+            for tile_idx in target_tiles(ecs, &effect.targets) {
+                affect_tile(ecs, effect, tile_idx);
+            }
+            if let Targets::Single { target } = effect.targets {
+                affect_entity(ecs, effect, target);
+            }
+        }
+    }
+}
+
+fn tile_effect_hits_entities(effect: &EffectType) -> bool {
+    matches!(
+        effect,
+        EffectType::Damage { .. }
+            | EffectType::Healing { .. }
+            | EffectType::Status { .. }
+            | EffectType::WellFed
+            | EffectType::TeleportTo { .. }
+    )
+}
+
+fn affect_tile(ecs: &mut World, effect: &EffectSpawner, tile_idx: i32) {
+    if tile_effect_hits_entities(&effect.effect_type) {
+        let content = ecs.fetch::<Map>().tile_content[tile_idx as usize].clone();
+        for entity in content.iter() {
+            affect_entity(ecs, effect, *entity);
+        }
+    }
+
+    if let EffectType::Particle { .. } = effect.effect_type {
+        particles::particle_to_tile(ecs, tile_idx, effect);
+    }
+}
+
+fn affect_entity(ecs: &mut World, effect: &EffectSpawner, target: Entity) {
+    match &effect.effect_type {
+        EffectType::Damage { .. } => damage::inflict_damage(ecs, effect, target),
+        EffectType::Healing { .. } => damage::heal_damage(ecs, effect, target),
+        EffectType::Status { .. } => status::add_status(ecs, effect, target),
+        EffectType::WellFed => hunger::well_fed(ecs, effect, target),
+        EffectType::TeleportTo { .. } => movement::apply_teleport(ecs, effect, target),
+        EffectType::Particle { .. } => {
+            // Tile targets are drawn by affect_tile; a single entity gets it where it stands
+            if let Targets::Single { .. } = effect.targets {
+                if let Some(pos) = entity_position(ecs, target) {
+                    particles::particle_to_tile(ecs, pos, effect);
+                }
+            }
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,50 @@
+use super::{EffectSpawner, EffectType};
+use crate::components::{BlocksTile, EntityMoved, Position, Viewshed};
+use crate::map::{tile_walkable, Map};
+use rltk::Point;
+use specs::prelude::*;
+
+/// Moves the target to a spot on the current map, as long as there is room to stand there.
+pub fn apply_teleport(ecs: &mut World, effect: &EffectSpawner, target: Entity) {
+    if let EffectType::TeleportTo { x, y } = effect.effect_type {
+        {
+            let map = ecs.fetch::<Map>();
+            if x < 0 || x >= map.width || y < 0 || y >= map.height {
+                return;
+            }
+            let idx = map.xy_idx(x, y);
+            if map.blocked[idx] || !tile_walkable(map.tiles[idx]) {
+                return;
+            }
+        }
+
+        let mut positions = ecs.write_storage::<Position>();
+        let pos = match positions.get_mut(target) {
+            Some(pos) => pos,
+            None => return,
+        };
+        // Keep the map's index in step until the next indexing pass
+        {
+            let mut map = ecs.write_resource::<Map>();
+            let old_idx = map.xy_idx(pos.x, pos.y);
+            let new_idx = map.xy_idx(x, y);
+            map.tile_content[old_idx].retain(|e| *e != target);
+            map.tile_content[new_idx].push(target);
+            if ecs.read_storage::<BlocksTile>().contains(target) {
+                map.blocked[old_idx] = false;
+                map.blocked[new_idx] = true;
+            }
+        }
+        pos.x = x;
+        pos.y = y;
+        if target == *ecs.fetch::<Entity>() {
+            *ecs.write_resource::<Point>() = Point::new(x, y);
+        }
+        if let Some(vs) = ecs.write_storage::<Viewshed>().get_mut(target) {
+            vs.dirty = true;
+        }
+        ecs.write_storage::<EntityMoved>()
+            .insert(target, EntityMoved {})
+            .expect("Unable to insert marker");
+    }
+}
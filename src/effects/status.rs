@@ -0,0 +1,29 @@
+use super::{EffectSpawner, EffectType};
+use crate::components::{Status, StatusEffects, StatusKind, Viewshed};
+use specs::prelude::*;
+
+pub fn add_status(ecs: &mut World, effect: &EffectSpawner, target: Entity) {
+    if let EffectType::Status {
+        kind,
+        turns,
+        amount,
+    } = effect.effect_type
+    {
+        StatusEffects::add_status(
+            &mut ecs.write_storage::<StatusEffects>(),
+            target,
+            Status {
+                kind,
+                turns,
+                amount,
+            },
+        );
+
+        // Going blind shrinks what you can see straight away
+        if kind == StatusKind::Blinded {
+            if let Some(vs) = ecs.write_storage::<Viewshed>().get_mut(target) {
+                vs.dirty = true;
+            }
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use super::Targets;
+use crate::components::Position;
+use crate::map::Map;
+use rltk::Point;
+use specs::prelude::*;
+
+/// The map index an entity is standing on, if it is on the map.
+pub fn entity_position(ecs: &World, target: Entity) -> Option<i32> {
+    let positions = ecs.read_storage::<Position>();
+    let map = ecs.fetch::<Map>();
+    positions
+        .get(target)
+        .map(|pos| map.xy_idx(pos.x, pos.y) as i32)
+}
+
+/// Every tile within `radius` of `center` that has a line of sight to it.
+pub fn aoe_tiles(map: &Map, center: Point, radius: i32) -> Vec<i32> {
+    let mut blast_tiles = rltk::field_of_view(center, radius, map);
+    blast_tiles.retain(|p| p.x > 0 && p.x < map.width - 1 && p.y > 0 && p.y < map.height - 1);
+    blast_tiles
+        .iter()
+        .map(|p| map.xy_idx(p.x, p.y) as i32)
+        .collect()
+}
+
+/// The tiles a target covers. Single-entity targets don't cover any tiles.
+pub fn target_tiles(ecs: &World, targets: &Targets) -> Vec<i32> {
+    match targets {
+        Targets::Single { .. } => Vec::new(),
+        Targets::Tile { tile_idx } => vec![*tile_idx],
+        Targets::Tiles { tiles } => tiles.clone(),
+        Targets::Area { center, radius } => {
+            let map = ecs.fetch::<Map>();
+            let center = Point::new(*center % map.width, *center / map.width);
+            aoe_tiles(&map, center, *radius)
+        }
+    }
+}
+
+/// The entities a target covers: the target itself, or everything standing on its tiles.
+pub fn target_entities(ecs: &World, targets: &Targets) -> Vec<Entity> {
+    if let Targets::Single { target } = targets {
+        return vec![*target];
+    }
+    let map = ecs.fetch::<Map>();
+    let mut entities = Vec::new();
+    for tile_idx in target_tiles(ecs, targets) {
+        for entity in map.tile_content[tile_idx as usize].iter() {
+            entities.push(*entity);
+        }
+    }
+    entities
+}
@@ -0,0 +1,244 @@
+use super::{add_effect, target_entities, target_tiles, EffectType, Targets};
+use crate::components::*;
+use crate::gamelog::GameLog;
+use crate::map::Map;
+use crate::RunState;
+use specs::prelude::*;
+
+/// Applies everything an item does to its targets, then uses it up if it's consumable.
+pub fn item_trigger(creator: Option<Entity>, item: Entity, targets: &Targets, ecs: &mut World) {
+    let did_something = event_trigger(creator, item, targets, ecs);
+
+    if did_something && ecs.read_storage::<Consumable>().get(item).is_some() {
+        ecs.entities().delete(item).expect("Delete failed");
+    }
+}
+
+/// Fires a trap (or anything else with an `EntryTrigger`) at whoever set it off.
+pub fn trigger(creator: Option<Entity>, trigger: Entity, targets: &Targets, ecs: &mut World) {
+    if let Some(name) = ecs.read_storage::<Name>().get(trigger) {
+        ecs.write_resource::<GameLog>()
+            .entries
+            .push(format!("{} triggers!", &name.name));
+    }
+
+    // The trap is no longer hidden
+    ecs.write_storage::<Hidden>().remove(trigger);
+
+    event_trigger(creator, trigger, targets, ecs);
+
+    // If it is single activation, it needs to be removed
+    if ecs
+        .read_storage::<SingleActivation>()
+        .get(trigger)
+        .is_some()
+    {
+        ecs.entities()
+            .delete(trigger)
+            .expect("Unable to delete trap");
+    }
+}
+
+/// Queues the effects of each of an entity's effect components. Returns true if it did
+/// anything, so failed uses don't use up the item.
+fn event_trigger(
+    creator: Option<Entity>,
+    entity: Entity,
+    targets: &Targets,
+    ecs: &mut World,
+) -> bool {
+    let mut did_something = false;
+    let player_entity = *ecs.fetch::<Entity>();
+    let by_player = creator == Some(player_entity);
+    let item_name = ecs
+        .read_storage::<Name>()
+        .get(entity)
+        .map(|n| n.name.clone())
+        .unwrap_or_default();
+    let victims: Vec<Entity> = {
+        let pools = ecs.read_storage::<Pools>();
+        target_entities(ecs, targets)
+            .into_iter()
+            .filter(|e| pools.get(*e).is_some())
+            .collect()
+    };
+    let victim_name = |ecs: &World, victim: Entity| {
+        ecs.read_storage::<Name>()
+            .get(victim)
+            .map(|n| n.name.clone())
+            .unwrap_or_default()
+    };
+
+    // Blasts light up the whole area
+    if let Targets::Area { .. } = targets {
+        add_effect(
+            None,
+            EffectType::Particle {
+                glyph: rltk::to_cp437('░'),
+                fg: rltk::RGB::named(rltk::ORANGE),
+                bg: rltk::RGB::named(rltk::BLACK),
+                lifespan: 200.0,
+            },
+            targets.clone(),
+        );
+    }
+
+    // Food
+    if ecs.read_storage::<ProvidesFood>().get(entity).is_some() {
+        for victim in victims.iter() {
+            add_effect(
+                creator,
+                EffectType::WellFed,
+                Targets::Single { target: *victim },
+            );
+        }
+        if by_player {
+            ecs.write_resource::<GameLog>()
+                .entries
+                .push(format!("You eat the {}.", item_name));
+        }
+        did_something = true;
+    }
+
+    // Magic mapping
+    if ecs.read_storage::<MagicMapper>().get(entity).is_some() {
+        ecs.write_resource::<GameLog>()
+            .entries
+            .push("The map is revealed to you!".to_string());
+        *ecs.write_resource::<RunState>() = RunState::MagicMapReveal { row: 0 };
+        did_something = true;
+    }
+
+    // Blinking moves the user to the targeted tile
+    if ecs.read_storage::<Blink>().get(entity).is_some() {
+        if let (Some(user), Some(tile_idx)) = (creator, target_tiles(ecs, targets).first()) {
+            let width = ecs.fetch::<Map>().width;
+            add_effect(
+                creator,
+                EffectType::TeleportTo {
+                    x: tile_idx % width,
+                    y: tile_idx / width,
+                },
+                Targets::Single { target: user },
+            );
+            if by_player {
+                ecs.write_resource::<GameLog>()
+                    .entries
+                    .push(format!("You read the {} and vanish!", item_name));
+            }
+            did_something = true;
+        }
+    }
+
+    // Healing
+    if let Some(heal) = ecs.read_storage::<ProvidesHealing>().get(entity) {
+        for victim in victims.iter() {
+            add_effect(
+                creator,
+                EffectType::Healing {
+                    amount: heal.heal_amount,
+                },
+                Targets::Single { target: *victim },
+            );
+            add_effect(
+                None,
+                EffectType::Particle {
+                    glyph: rltk::to_cp437('♥'),
+                    fg: rltk::RGB::named(rltk::GREEN),
+                    bg: rltk::RGB::named(rltk::BLACK),
+                    lifespan: 200.0,
+                },
+                Targets::Single { target: *victim },
+            );
+            if by_player {
+                ecs.write_resource::<GameLog>().entries.push(format!(
+                    "You use the {}, healing {} hp.",
+                    item_name, heal.heal_amount
+                ));
+            }
+            did_something = true;
+        }
+    }
+
+    // Damage
+    if let Some(damage) = ecs.read_storage::<InflictsDamage>().get(entity) {
+        for victim in victims.iter() {
+            add_effect(
+                creator,
+                EffectType::Damage {
+                    amount: damage.damage,
+                },
+                Targets::Single { target: *victim },
+            );
+            add_effect(
+                None,
+                EffectType::Particle {
+                    glyph: rltk::to_cp437('‼'),
+                    fg: rltk::RGB::named(rltk::RED),
+                    bg: rltk::RGB::named(rltk::BLACK),
+                    lifespan: 200.0,
+                },
+                Targets::Single { target: *victim },
+            );
+            if by_player {
+                let mob_name = victim_name(ecs, *victim);
+                ecs.write_resource::<GameLog>().entries.push(format!(
+                    "You use {} on {}, inflicting {} hp.",
+                    item_name, mob_name, damage.damage
+                ));
+            }
+            did_something = true;
+        }
+    }
+
+    // Status effects (poison, haste, confusion and so on)
+    if let Some(inflicts) = ecs.read_storage::<InflictsStatus>().get(entity) {
+        for victim in victims.iter() {
+            for spec in inflicts.statuses.iter() {
+                let amount = if spec.n_dice > 0 {
+                    ecs.write_resource::<rltk::RandomNumberGenerator>()
+                        .roll_dice(spec.n_dice, spec.die_type)
+                        + spec.bonus
+                } else {
+                    0
+                };
+                add_effect(
+                    creator,
+                    EffectType::Status {
+                        kind: spec.kind,
+                        turns: spec.turns,
+                        amount,
+                    },
+                    Targets::Single { target: *victim },
+                );
+            }
+            add_effect(
+                None,
+                EffectType::Particle {
+                    glyph: rltk::to_cp437('?'),
+                    fg: rltk::RGB::named(rltk::MAGENTA),
+                    bg: rltk::RGB::named(rltk::BLACK),
+                    lifespan: 200.0,
+                },
+                Targets::Single { target: *victim },
+            );
+            if by_player {
+                let mob_name = victim_name(ecs, *victim);
+                let effects: Vec<&str> = inflicts
+                    .statuses
+                    .iter()
+                    .map(|spec| spec.kind.name())
+                    .collect();
+                ecs.write_resource::<GameLog>().entries.push(format!(
+                    "You use {} on {}: {}.",
+                    item_name,
+                    mob_name,
+                    effects.join(", ")
+                ));
+            }
+            did_something = true;
+        }
+    }
+
+    did_something
+}
@@ -0,0 +1,58 @@
+use super::{Skill, Skills, StatusEffects, StatusKind};
+
+/// The bonus (or penalty) an attribute gives to rolls: +1 for every two points above 10.
+pub fn attr_bonus(value: i32) -> i32 {
+    (value - 10) / 2
+}
+
+pub fn player_hp_per_level(fitness: i32) -> i32 {
+    15 + attr_bonus(fitness)
+}
+
+pub fn player_hp_at_level(fitness: i32, level: i32) -> i32 {
+    15 + player_hp_per_level(fitness) * level
+}
+
+pub fn npc_hp(fitness: i32, level: i32) -> i32 {
+    let mut total = 1;
+    for _i in 0..level {
+        total += i32::max(1, 8 + attr_bonus(fitness));
+    }
+    total
+}
+
+/// Experience awarded for killing something of the given level.
+pub fn xp_for_kill(victim_level: i32) -> i32 {
+    victim_level * 100
+}
+
+/// Total experience needed to advance past `level`.
+pub fn xp_to_next_level(level: i32) -> i32 {
+    level * 1000
+}
+
+/// To-hit modifier from the attacker's status effects.
+pub fn status_hit_modifier(statuses: Option<&StatusEffects>) -> i32 {
+    let mut bonus = 0;
+    if let Some(statuses) = statuses {
+        if statuses.has(StatusKind::Blinded) {
+            bonus -= 4;
+        }
+        if statuses.has(StatusKind::Hasted) {
+            bonus += 1;
+        }
+        if statuses.has(StatusKind::Slowed) {
+            bonus -= 1;
+        }
+    }
+    bonus
+}
+
+/// Untrained skills are a liability.
+pub fn skill_bonus(skill: Skill, skills: &Skills) -> i32 {
+    if let Some(level) = skills.skills.get(&skill) {
+        *level
+    } else {
+        -4
+    }
+}
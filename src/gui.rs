@@ -1,6 +1,8 @@
 use super::{
-    camera, gamelog::GameLog, rex_assets::RexAssets, CombatStats, Equipped, Hidden, HungerClock,
-    HungerState, InBackpack, Map, Name, Player, Position, RunState, State, Viewshed,
+    camera, gamelog::GameLog, gamesystem::xp_to_next_level, raws::*, rex_assets::RexAssets,
+    save_storage, saveload_system, Dialogue, Equipped, Hidden, HungerClock, HungerState,
+    InBackpack, Item, Map, MasterDungeonMap, Name, Player, Pools, Position, RunState, State,
+    StatusEffects, StatusKind, Vendor, VendorMode, Viewshed,
 };
 use rltk::{Point, Rltk, VirtualKeyCode, RGB};
 use specs::prelude::*;
@@ -15,11 +17,14 @@ pub fn draw_ui(ecs: &World, ctx: &mut Rltk) {
         RGB::named(rltk::BLACK),
     );
 
-    let combat_stats = ecs.read_storage::<CombatStats>();
+    let combat_stats = ecs.read_storage::<Pools>();
     let players = ecs.read_storage::<Player>();
     let hunger = ecs.read_storage::<HungerClock>();
     for (_player, stats, hc) in (&players, &combat_stats, &hunger).join() {
-        let health = format!(" HP: {} / {} ", stats.hp, stats.max_hp);
+        let health = format!(
+            " HP: {} / {} ",
+            stats.hit_points.current, stats.hit_points.max
+        );
         ctx.print_color(
             12,
             43,
@@ -31,13 +36,30 @@ pub fn draw_ui(ecs: &World, ctx: &mut Rltk) {
         ctx.draw_bar_horizontal(
             28,
             43,
-            51,
-            stats.hp,
-            stats.max_hp,
+            25,
+            stats.hit_points.current,
+            stats.hit_points.max,
             RGB::named(rltk::RED),
             RGB::named(rltk::BLACK),
         );
 
+        let xp = format!(
+            " Lvl {} XP {}/{} ",
+            stats.level,
+            stats.xp,
+            xp_to_next_level(stats.level)
+        );
+        ctx.print_color(54, 43, RGB::named(rltk::GOLD), RGB::named(rltk::BLACK), &xp);
+
+        let gold = format!(" Gold: {:.1} ", stats.gold);
+        ctx.print_color(
+            62,
+            49,
+            RGB::named(rltk::GOLD),
+            RGB::named(rltk::BLACK),
+            &gold,
+        );
+
         match hc.state {
             HungerState::WellFed => ctx.print_color(
                 71,
@@ -64,6 +86,39 @@ pub fn draw_ui(ecs: &World, ctx: &mut Rltk) {
         }
     }
 
+    // Status bar: the player's active status effects, just above the log
+    let player_entity = ecs.fetch::<Entity>();
+    let status_effects = ecs.read_storage::<StatusEffects>();
+    if let Some(statuses) = status_effects.get(*player_entity) {
+        let mut x = 1;
+        let mut shown: Vec<StatusKind> = Vec::new();
+        for status in statuses.effects.iter() {
+            if shown.contains(&status.kind) {
+                continue;
+            }
+            shown.push(status.kind);
+            let turns = statuses
+                .effects
+                .iter()
+                .filter(|s| s.kind == status.kind)
+                .map(|s| s.turns)
+                .max()
+                .unwrap_or(0);
+            let color = match status.kind {
+                StatusKind::Poisoned => RGB::named(rltk::GREEN),
+                StatusKind::Regenerating => RGB::named(rltk::PINK),
+                StatusKind::Hasted => RGB::named(rltk::CYAN),
+                StatusKind::Slowed => RGB::named(rltk::BROWN1),
+                StatusKind::Stunned => RGB::named(rltk::YELLOW),
+                StatusKind::Blinded => RGB::named(rltk::GREY),
+                StatusKind::Confused => RGB::named(rltk::MAGENTA),
+            };
+            let label = format!("{}({})", status.kind.name(), turns);
+            ctx.print_color(x, 42, color, RGB::named(rltk::BLACK), &label);
+            x += label.len() as i32 + 1;
+        }
+    }
+
     let map = ecs.fetch::<Map>();
     let depth = format!("Depth: {}", map.depth);
     ctx.print_color(
@@ -531,6 +586,411 @@ pub fn ranged_target(
     (ItemMenuResult::NoResponse, None)
 }
 
+#[derive(PartialEq, Copy, Clone)]
+pub enum DialogueResult {
+    NoResponse,
+    Done,
+    Next,
+    Trade,
+}
+
+/// Breaks text into lines no longer than `width`, splitting between words.
+fn wrap_text(text: &str, width: usize) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        if !line.is_empty() && line.len() + word.len() + 1 > width {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// Shows what an NPC has to say at the current node of their dialogue tree, and lets the
+/// player pick a response. Returns the next node to show, if the conversation goes on.
+pub fn show_dialogue(
+    gs: &mut State,
+    ctx: &mut Rltk,
+    speaker: Entity,
+    node: usize,
+) -> (DialogueResult, Option<usize>) {
+    let dialogues = gs.ecs.read_storage::<Dialogue>();
+    let names = gs.ecs.read_storage::<Name>();
+    let raws = RAWS.lock().unwrap();
+    let tree = match dialogues.get(speaker) {
+        Some(dialogue) => dialogue.tree.clone(),
+        None => return (DialogueResult::Done, None),
+    };
+    let current = match dialogue_node(&raws, &tree, node) {
+        Some(current) => current,
+        None => return (DialogueResult::Done, None),
+    };
+    let speaker_name = names
+        .get(speaker)
+        .map(|n| n.name.clone())
+        .unwrap_or_default();
+
+    let lines = wrap_text(&current.text, 54);
+    let count = lines.len() + current.responses.len() + 1;
+    let y = (25 - (count / 2)) as i32;
+    ctx.draw_box(
+        10,
+        y - 2,
+        59,
+        (count + 3) as i32,
+        RGB::named(rltk::WHITE),
+        RGB::named(rltk::BLACK),
+    );
+    ctx.print_color(
+        13,
+        y - 2,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        &speaker_name,
+    );
+    ctx.print_color(
+        13,
+        y + count as i32 + 1,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        "ESCAPE to leave",
+    );
+
+    for (i, line) in lines.iter().enumerate() {
+        ctx.print(13, y + i as i32, line);
+    }
+    let responses_y = y + lines.len() as i32 + 1;
+    for (j, response) in current.responses.iter().enumerate() {
+        draw_menu_letter(ctx, 12, responses_y + j as i32, j);
+        ctx.print_color(
+            16,
+            responses_y + j as i32,
+            RGB::named(rltk::CYAN),
+            RGB::named(rltk::BLACK),
+            &response.text,
+        );
+    }
+
+    match ctx.key {
+        None => (DialogueResult::NoResponse, None),
+        Some(key) => match key {
+            VirtualKeyCode::Escape => (DialogueResult::Done, None),
+            _ => {
+                let selection = rltk::letter_to_option(key);
+                if selection < 0 || selection >= current.responses.len() as i32 {
+                    return (DialogueResult::NoResponse, None);
+                }
+                let response = &current.responses[selection as usize];
+                if response.action.as_deref() == Some("trade") {
+                    return (DialogueResult::Trade, None);
+                }
+                match response
+                    .next
+                    .as_ref()
+                    .and_then(|next| dialogue_node_index(&raws, &tree, next))
+                {
+                    Some(next) => (DialogueResult::Next, Some(next)),
+                    None => (DialogueResult::Done, None),
+                }
+            }
+        },
+    }
+}
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum VendorResult {
+    NoResponse,
+    Cancel,
+    Sell,
+    BuyMode,
+    SellMode,
+    Buy,
+}
+
+fn draw_menu_letter(ctx: &mut Rltk, x: i32, y: i32, j: usize) {
+    ctx.set(
+        x,
+        y,
+        RGB::named(rltk::WHITE),
+        RGB::named(rltk::BLACK),
+        rltk::to_cp437('('),
+    );
+    ctx.set(
+        x + 1,
+        y,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        97 + j as rltk::FontCharType,
+    );
+    ctx.set(
+        x + 2,
+        y,
+        RGB::named(rltk::WHITE),
+        RGB::named(rltk::BLACK),
+        rltk::to_cp437(')'),
+    );
+}
+
+/// Lists what the player could sell from their backpack, and what each item would fetch.
+fn vendor_sell_menu(
+    gs: &mut State,
+    ctx: &mut Rltk,
+    _vendor: Entity,
+) -> (VendorResult, Option<Entity>, Option<String>, Option<f32>) {
+    let player_entity = gs.ecs.fetch::<Entity>();
+    let names = gs.ecs.read_storage::<Name>();
+    let backpack = gs.ecs.read_storage::<InBackpack>();
+    let items = gs.ecs.read_storage::<Item>();
+    let entities = gs.ecs.entities();
+
+    let inventory: Vec<(Entity, String, f32)> = (&entities, &backpack, &names, &items)
+        .join()
+        .filter(|item| item.1.owner == *player_entity)
+        .map(|(entity, _pack, name, item)| (entity, name.name.clone(), item.base_value * 0.8))
+        .collect();
+    let count = inventory.len();
+
+    let y = (25 - (count / 2)) as i32;
+    ctx.draw_box(
+        15,
+        y - 2,
+        51,
+        (count + 3) as i32,
+        RGB::named(rltk::WHITE),
+        RGB::named(rltk::BLACK),
+    );
+    ctx.print_color(
+        18,
+        y - 2,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        "Sell Which Item? (space to switch to buy mode)",
+    );
+    ctx.print_color(
+        18,
+        y + count as i32 + 1,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        "ESCAPE to cancel",
+    );
+
+    for (j, (_entity, name, price)) in inventory.iter().enumerate() {
+        let row = y + j as i32;
+        draw_menu_letter(ctx, 17, row, j);
+        ctx.print(21, row, name);
+        ctx.print(50, row, format!("{:.1} gp", price));
+    }
+
+    match ctx.key {
+        None => (VendorResult::NoResponse, None, None, None),
+        Some(key) => match key {
+            VirtualKeyCode::Space => (VendorResult::BuyMode, None, None, None),
+            VirtualKeyCode::Escape => (VendorResult::Cancel, None, None, None),
+            _ => {
+                let selection = rltk::letter_to_option(key);
+                if selection > -1 && selection < count as i32 {
+                    let (entity, _, price) = &inventory[selection as usize];
+                    return (VendorResult::Sell, Some(*entity), None, Some(*price));
+                }
+                (VendorResult::NoResponse, None, None, None)
+            }
+        },
+    }
+}
+
+/// Lists the vendor's stock and prices.
+fn vendor_buy_menu(
+    gs: &mut State,
+    ctx: &mut Rltk,
+    vendor: Entity,
+) -> (VendorResult, Option<Entity>, Option<String>, Option<f32>) {
+    let vendors = gs.ecs.read_storage::<Vendor>();
+    let inventory = match vendors.get(vendor) {
+        Some(stock) => get_vendor_items(&stock.categories, &RAWS.lock().unwrap()),
+        None => return (VendorResult::Cancel, None, None, None),
+    };
+    let count = inventory.len();
+
+    let y = (25 - (count / 2)) as i32;
+    ctx.draw_box(
+        15,
+        y - 2,
+        51,
+        (count + 3) as i32,
+        RGB::named(rltk::WHITE),
+        RGB::named(rltk::BLACK),
+    );
+    ctx.print_color(
+        18,
+        y - 2,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        "Buy Which Item? (space to switch to sell mode)",
+    );
+    ctx.print_color(
+        18,
+        y + count as i32 + 1,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        "ESCAPE to cancel",
+    );
+
+    for (j, (name, price)) in inventory.iter().enumerate() {
+        let row = y + j as i32;
+        draw_menu_letter(ctx, 17, row, j);
+        ctx.print(21, row, name);
+        ctx.print(50, row, format!("{:.1} gp", price));
+    }
+
+    match ctx.key {
+        None => (VendorResult::NoResponse, None, None, None),
+        Some(key) => match key {
+            VirtualKeyCode::Space => (VendorResult::SellMode, None, None, None),
+            VirtualKeyCode::Escape => (VendorResult::Cancel, None, None, None),
+            _ => {
+                let selection = rltk::letter_to_option(key);
+                if selection > -1 && selection < count as i32 {
+                    let (name, price) = &inventory[selection as usize];
+                    return (VendorResult::Buy, None, Some(name.clone()), Some(*price));
+                }
+                (VendorResult::NoResponse, None, None, None)
+            }
+        },
+    }
+}
+
+pub fn show_vendor_menu(
+    gs: &mut State,
+    ctx: &mut Rltk,
+    vendor: Entity,
+    mode: VendorMode,
+) -> (VendorResult, Option<Entity>, Option<String>, Option<f32>) {
+    match mode {
+        VendorMode::Buy => vendor_buy_menu(gs, ctx, vendor),
+        VendorMode::Sell => vendor_sell_menu(gs, ctx, vendor),
+    }
+}
+
+#[derive(PartialEq, Copy, Clone)]
+pub enum SaveSlotResult {
+    NoResponse,
+    Cancel,
+    Selected,
+}
+
+/// How long ago a save was made, in the largest unit that fits.
+fn save_age(timestamp: u64) -> String {
+    let age = save_storage::unix_time().saturating_sub(timestamp);
+    if age < 60 {
+        "just now".to_string()
+    } else if age < 60 * 60 {
+        format!("{}m ago", age / 60)
+    } else if age < 60 * 60 * 24 {
+        format!("{}h ago", age / (60 * 60))
+    } else {
+        format!("{}d ago", age / (60 * 60 * 24))
+    }
+}
+
+/// Lists the save slots with a summary of each. When saving any slot can be picked
+/// (overwriting what's there); when loading only slots with a save in them.
+pub fn save_slot_menu(ctx: &mut Rltk, saving: bool) -> (SaveSlotResult, Option<usize>) {
+    let slots = saveload_system::save_slots();
+    let count = slots.len();
+
+    // Each slot takes two rows: a summary, then the seed
+    let y = (25 - count) as i32;
+    ctx.draw_box(
+        10,
+        y - 2,
+        61,
+        (count * 2 + 2) as i32,
+        RGB::named(rltk::WHITE),
+        RGB::named(rltk::BLACK),
+    );
+    ctx.print_color(
+        13,
+        y - 2,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        if saving {
+            "Save To Which Slot?"
+        } else {
+            "Load Which Save?"
+        },
+    );
+    ctx.print_color(
+        13,
+        y + count as i32 * 2,
+        RGB::named(rltk::YELLOW),
+        RGB::named(rltk::BLACK),
+        "ESCAPE to cancel",
+    );
+
+    for (j, slot) in slots.iter().enumerate() {
+        let row = y + j as i32 * 2;
+        draw_menu_letter(ctx, 12, row, j);
+        match slot {
+            saveload_system::SaveSlot::Empty => ctx.print_color(
+                16,
+                row,
+                RGB::named(rltk::GRAY),
+                RGB::named(rltk::BLACK),
+                "Empty",
+            ),
+            saveload_system::SaveSlot::Unknown => ctx.print(16, row, "Unknown save"),
+            saveload_system::SaveSlot::Saved(meta) => {
+                ctx.print(
+                    16,
+                    row,
+                    format!(
+                        "Level {}, depth {}, {} turns",
+                        meta.level, meta.depth, meta.turns
+                    ),
+                );
+                ctx.print_color(
+                    60,
+                    row,
+                    RGB::named(rltk::GRAY),
+                    RGB::named(rltk::BLACK),
+                    save_age(meta.timestamp),
+                );
+                ctx.print_color(
+                    16,
+                    row + 1,
+                    RGB::named(rltk::GRAY),
+                    RGB::named(rltk::BLACK),
+                    format!("Seed: {}", meta.seed),
+                );
+            }
+        }
+    }
+
+    match ctx.key {
+        None => (SaveSlotResult::NoResponse, None),
+        Some(key) => match key {
+            VirtualKeyCode::Escape => (SaveSlotResult::Cancel, None),
+            _ => {
+                let selection = rltk::letter_to_option(key);
+                if selection > -1 && selection < count as i32 {
+                    let slot = selection as usize;
+                    if saving || !matches!(slots[slot], saveload_system::SaveSlot::Empty) {
+                        return (SaveSlotResult::Selected, Some(slot));
+                    }
+                }
+                (SaveSlotResult::NoResponse, None)
+            }
+        },
+    }
+}
+
 #[derive(PartialEq, Copy, Clone)]
 pub enum MainMenuSelection {
     NewGame,
@@ -548,6 +1008,7 @@ pub fn main_menu(gs: &mut State, ctx: &mut Rltk) -> MainMenuResult {
     let save_exists = super::saveload_system::does_save_exist();
     let runstate = gs.ecs.fetch::<RunState>();
     let assets = gs.ecs.fetch::<RexAssets>();
+    let seed = gs.ecs.fetch::<MasterDungeonMap>().seed;
     ctx.render_xp_sprite(&assets.menu, 0, 0);
 
     ctx.draw_box_double(
@@ -630,6 +1091,14 @@ pub fn main_menu(gs: &mut State, ctx: &mut Rltk) -> MainMenuResult {
             ctx.print_color_centered(y, RGB::named(rltk::WHITE), RGB::named(rltk::BLACK), "Quit");
         }
 
+        let seed_text = format!("Seed: {}", seed);
+        ctx.print_color_centered(
+            29,
+            RGB::named(rltk::GRAY),
+            RGB::named(rltk::BLACK),
+            &seed_text,
+        );
+
         match ctx.key {
             None => {
                 return MainMenuResult::NoSelection {
@@ -695,7 +1164,9 @@ pub enum GameOverResult {
     QuitToMenu,
 }
 
-pub fn game_over(ctx: &mut Rltk) -> GameOverResult {
+pub fn game_over(ecs: &World, ctx: &mut Rltk) -> GameOverResult {
+    let seed = ecs.fetch::<MasterDungeonMap>().seed;
+
     ctx.print_color_centered(
         15,
         RGB::named(rltk::YELLOW),
@@ -715,8 +1186,16 @@ pub fn game_over(ctx: &mut Rltk) -> GameOverResult {
         "That day, sadly, is not in this chapter..",
     );
 
+    let seed_text = format!("This dungeon was generated from seed {}.", seed);
     ctx.print_color_centered(
-        20,
+        19,
+        RGB::named(rltk::GRAY),
+        RGB::named(rltk::BLACK),
+        &seed_text,
+    );
+
+    ctx.print_color_centered(
+        21,
         RGB::named(rltk::MAGENTA),
         RGB::named(rltk::BLACK),
         "Press any key to return to the menu.",
@@ -727,3 +1206,43 @@ pub fn game_over(ctx: &mut Rltk) -> GameOverResult {
         Some(_) => GameOverResult::QuitToMenu,
     }
 }
+
+/// Explains why a saved game couldn't be loaded, instead of crashing.
+pub fn load_failed(error: &str, ctx: &mut Rltk) -> GameOverResult {
+    saveload_error(
+        "Unable to load the saved game!",
+        error,
+        "Press any key to return to the menu.",
+        ctx,
+    )
+}
+
+/// Explains why the game couldn't be saved. The game carries on, so the player can try again.
+pub fn save_failed(error: &str, ctx: &mut Rltk) -> GameOverResult {
+    saveload_error(
+        "Unable to save the game!",
+        error,
+        "Press any key to return to the game.",
+        ctx,
+    )
+}
+
+fn saveload_error(heading: &str, error: &str, prompt: &str, ctx: &mut Rltk) -> GameOverResult {
+    ctx.print_color_centered(15, RGB::named(rltk::RED), RGB::named(rltk::BLACK), heading);
+    let mut y = 17;
+    for line in wrap_text(error, 70) {
+        ctx.print_color_centered(y, RGB::named(rltk::WHITE), RGB::named(rltk::BLACK), &line);
+        y += 1;
+    }
+    ctx.print_color_centered(
+        y + 1,
+        RGB::named(rltk::MAGENTA),
+        RGB::named(rltk::BLACK),
+        prompt,
+    );
+
+    match ctx.key {
+        None => GameOverResult::NoSelection,
+        Some(_) => GameOverResult::QuitToMenu,
+    }
+}
@@ -0,0 +1,98 @@
+use super::{
+    Attributes, Equipped, Initiative, MasterDungeonMap, MyTurn, Position, RunState, StatusEffects,
+    StatusKind, Wearable,
+};
+use specs::prelude::*;
+use std::collections::HashMap;
+
+/// Counts everyone's initiative down, handing out `MyTurn` to whoever reaches zero. Quick
+/// creatures come round again sooner; heavy armor and slowness make you wait. When it's the
+/// player's turn the game stops ticking and waits for input.
+pub struct InitiativeSystem {}
+
+impl<'a> System<'a> for InitiativeSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        WriteStorage<'a, Initiative>,
+        ReadStorage<'a, Position>,
+        WriteStorage<'a, MyTurn>,
+        Entities<'a>,
+        WriteExpect<'a, rltk::RandomNumberGenerator>,
+        ReadStorage<'a, Attributes>,
+        WriteExpect<'a, RunState>,
+        ReadExpect<'a, Entity>,
+        ReadStorage<'a, StatusEffects>,
+        ReadStorage<'a, Wearable>,
+        ReadStorage<'a, Equipped>,
+        WriteExpect<'a, MasterDungeonMap>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            mut initiatives,
+            positions,
+            mut turns,
+            entities,
+            mut rng,
+            attributes,
+            mut runstate,
+            player,
+            status_effects,
+            wearables,
+            equipped,
+            mut dungeon_master,
+        ) = data;
+
+        if *runstate != RunState::Ticking {
+            return;
+        }
+
+        // Clear any remaining MyTurn we left by mistake
+        turns.clear();
+
+        let mut armor_weight: HashMap<Entity, f32> = HashMap::new();
+        for (wearable, equipped_by) in (&wearables, &equipped).join() {
+            *armor_weight.entry(equipped_by.owner).or_insert(0.0) += wearable.weight_lbs;
+        }
+
+        // Roll initiative
+        for (entity, initiative, _pos) in (&entities, &mut initiatives, &positions).join() {
+            initiative.current -= 1;
+            if initiative.current < 1 {
+                // It's my turn!
+                initiative.current = 6 + rng.roll_dice(1, 6);
+
+                // Give a bonus for quickness
+                if let Some(attr) = attributes.get(entity) {
+                    initiative.current -= attr.quickness.bonus;
+                }
+
+                // Every ten pounds of armor costs a tick
+                if let Some(weight) = armor_weight.get(&entity) {
+                    initiative.current += (*weight / 10.0) as i32;
+                }
+
+                // Haste halves the wait, slowness doubles it
+                if let Some(statuses) = status_effects.get(entity) {
+                    if statuses.has(StatusKind::Hasted) {
+                        initiative.current /= 2;
+                    }
+                    if statuses.has(StatusKind::Slowed) {
+                        initiative.current *= 2;
+                    }
+                }
+                initiative.current = i32::max(1, initiative.current);
+
+                turns
+                    .insert(entity, MyTurn {})
+                    .expect("Unable to insert turn");
+
+                // If its the player, we want to go to an AwaitingInput state
+                if entity == *player {
+                    dungeon_master.turns += 1;
+                    *runstate = RunState::AwaitingInput;
+                }
+            }
+        }
+    }
+}
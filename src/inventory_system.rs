@@ -1,8 +1,8 @@
 use super::{
-    gamelog::GameLog, particle_system::ParticleBuilder, AreaOfEffect, CombatStats, Confusion,
-    Consumable, Equippable, Equipped, HungerClock, HungerState, InBackpack, InflictsDamage,
-    MagicMapper, Map, Name, Position, ProvidesFood, ProvidesHealing, RunState, SufferDamage,
-    WantsToDropItem, WantsToPickupItem, WantsToRemoveItem, WantsToUseItem,
+    effects::{add_effect, EffectType, Targets},
+    gamelog::GameLog,
+    AreaOfEffect, Equippable, Equipped, InBackpack, Map, Name, Position, WantsToDropItem,
+    WantsToPickupItem, WantsToRemoveItem, WantsToUseItem,
 };
 use specs::prelude::*;
 
@@ -53,29 +53,16 @@ impl<'a> System<'a> for ItemUseSystem {
     type SystemData = (
         ReadExpect<'a, Entity>,
         WriteExpect<'a, GameLog>,
-        WriteExpect<'a, Map>,
+        ReadExpect<'a, Map>,
         Entities<'a>,
         WriteStorage<'a, WantsToUseItem>,
         ReadStorage<'a, Name>,
-        ReadStorage<'a, Consumable>,
-        ReadStorage<'a, ProvidesHealing>,
-        ReadStorage<'a, InflictsDamage>,
-        WriteStorage<'a, CombatStats>,
-        WriteStorage<'a, SufferDamage>,
         ReadStorage<'a, AreaOfEffect>,
-        WriteStorage<'a, Confusion>,
         ReadStorage<'a, Equippable>,
         WriteStorage<'a, Equipped>,
         WriteStorage<'a, InBackpack>,
-        WriteExpect<'a, ParticleBuilder>,
-        ReadStorage<'a, Position>,
-        ReadStorage<'a, ProvidesFood>,
-        WriteStorage<'a, HungerClock>,
-        ReadStorage<'a, MagicMapper>,
-        WriteExpect<'a, RunState>,
     );
 
-    #[allow(clippy::cognitive_complexity)]
     fn run(&mut self, data: Self::SystemData) {
         let (
             player_entity,
@@ -84,76 +71,40 @@ impl<'a> System<'a> for ItemUseSystem {
             entities,
             mut wants_use,
             names,
-            consumables,
-            healing,
-            inflict_damage,
-            mut combat_stats,
-            mut suffer_damage,
             aoe,
-            mut confused,
             equippable,
             mut equipped,
             mut backpack,
-            mut particle_builder,
-            positions,
-            provides_food,
-            mut hunger_clocks,
-            magic_mapper,
-            mut runstate,
         ) = data;
 
         for (entity, useitem) in (&entities, &wants_use).join() {
-            let mut used_item = true;
-
-            // Targeting
-            let mut targets: Vec<Entity> = Vec::new();
-            match useitem.target {
+            // If it is equippable, then we want to equip it - and unequip whatever else was in that slot
+            let item_equippable = equippable.get(useitem.item);
+            match item_equippable {
                 None => {
-                    targets.push(*player_entity);
-                }
-                Some(target) => {
-                    let area_effect = aoe.get(useitem.item);
-                    match area_effect {
-                        None => {
-                            // Single target in tile
-                            let idx = map.xy_idx(target.x, target.y);
-                            for mob in map.tile_content[idx].iter() {
-                                targets.push(*mob);
-                            }
-                        }
-                        Some(area_effect) => {
-                            // AoE
-                            let mut blast_tiles =
-                                rltk::field_of_view(target, area_effect.radius, &*map);
-                            blast_tiles.retain(|p| {
-                                p.x > 0 && p.x < map.width - 1 && p.y > 0 && p.y < map.height - 1
-                            });
-                            for tile_idx in blast_tiles.iter() {
-                                let idx = map.xy_idx(tile_idx.x, tile_idx.y);
-                                for mob in map.tile_content[idx].iter() {
-                                    targets.push(*mob);
-                                }
-                                particle_builder.request(
-                                    tile_idx.x,
-                                    tile_idx.y,
-                                    rltk::RGB::named(rltk::ORANGE),
-                                    rltk::RGB::named(rltk::BLACK),
-                                    rltk::to_cp437('░'),
-                                    200.0,
-                                );
+                    // Everything else the item does goes through the effects queue
+                    let targets = match useitem.target {
+                        None => Targets::Single { target: entity },
+                        Some(target) => {
+                            let tile_idx = map.xy_idx(target.x, target.y) as i32;
+                            match aoe.get(useitem.item) {
+                                None => Targets::Tile { tile_idx },
+                                Some(area_effect) => Targets::Area {
+                                    center: tile_idx,
+                                    radius: area_effect.radius,
+                                },
                             }
                         }
-                    }
+                    };
+                    add_effect(
+                        Some(entity),
+                        EffectType::ItemUse { item: useitem.item },
+                        targets,
+                    );
                 }
-            }
-
-            // If it is equippable, then we want to equip it - and unequip whatever else was in that slot
-            let item_equippable = equippable.get(useitem.item);
-            match item_equippable {
-                None => {}
                 Some(can_equip) => {
                     let target_slot = can_equip.slot;
-                    let target = targets[0];
+                    let target = entity;
 
                     // Remove any items the target has in the item's slot
                     let mut to_unequip: Vec<Entity> = Vec::new();
@@ -194,158 +145,6 @@ impl<'a> System<'a> for ItemUseSystem {
                     }
                 }
             }
-
-            // It it is edible, eat it!
-            let item_edible = provides_food.get(useitem.item);
-            match item_edible {
-                None => {}
-                Some(_) => {
-                    used_item = true;
-                    let target = targets[0];
-                    let hc = hunger_clocks.get_mut(target);
-                    if let Some(hc) = hc {
-                        hc.state = HungerState::WellFed;
-                        hc.duration = 20;
-                        gamelog.entries.push(format!(
-                            "You eat the {}.",
-                            names.get(useitem.item).unwrap().name
-                        ));
-                    }
-                }
-            }
-
-            // If its a magic mapper...
-            let is_mapper = magic_mapper.get(useitem.item);
-            match is_mapper {
-                None => {}
-                Some(_) => {
-                    used_item = true;
-                    gamelog
-                        .entries
-                        .push("The map is revealed to you!".to_string());
-                    *runstate = RunState::MagicMapReveal { row: 0 };
-                }
-            }
-
-            // If it heals, apply the healing
-            let item_heals = healing.get(useitem.item);
-            match item_heals {
-                None => {}
-                Some(healer) => {
-                    used_item = false;
-                    for target in targets.iter() {
-                        let stats = combat_stats.get_mut(*target);
-                        if let Some(stats) = stats {
-                            stats.hp = i32::min(stats.max_hp, stats.hp + healer.heal_amount);
-                            if entity == *player_entity {
-                                gamelog.entries.push(format!(
-                                    "You use the {}, healing {} hp.",
-                                    names.get(useitem.item).unwrap().name,
-                                    healer.heal_amount
-                                ));
-                            }
-                            used_item = true;
-
-                            let pos = positions.get(*target);
-                            if let Some(pos) = pos {
-                                particle_builder.request(
-                                    pos.x,
-                                    pos.y,
-                                    rltk::RGB::named(rltk::GREEN),
-                                    rltk::RGB::named(rltk::BLACK),
-                                    rltk::to_cp437('♥'),
-                                    200.0,
-                                );
-                            }
-                        }
-                    }
-                }
-            }
-
-            // If it inflicts damage, apply it to the target cell
-            let item_damages = inflict_damage.get(useitem.item);
-            match item_damages {
-                None => {}
-                Some(damage) => {
-                    used_item = false;
-                    for mob in targets.iter() {
-                        SufferDamage::new_damage(&mut suffer_damage, *mob, damage.damage);
-                        if entity == *player_entity {
-                            let mob_name = names.get(*mob).unwrap();
-                            let item_name = names.get(useitem.item).unwrap();
-                            gamelog.entries.push(format!(
-                                "You use {} on {}, inflicting {} hp.",
-                                item_name.name, mob_name.name, damage.damage
-                            ));
-
-                            let pos = positions.get(*mob);
-                            if let Some(pos) = pos {
-                                particle_builder.request(
-                                    pos.x,
-                                    pos.y,
-                                    rltk::RGB::named(rltk::RED),
-                                    rltk::RGB::named(rltk::BLACK),
-                                    rltk::to_cp437('‼'),
-                                    200.0,
-                                );
-                            }
-                        }
-
-                        used_item = true;
-                    }
-                }
-            }
-
-            // Can it pass along confusion? Note the use of scopes to escape from the borrow checker!
-            let mut add_confusion = Vec::new();
-            {
-                let causes_confusion = confused.get(useitem.item);
-                match causes_confusion {
-                    None => {}
-                    Some(confusion) => {
-                        used_item = false;
-                        for mob in targets.iter() {
-                            add_confusion.push((*mob, confusion.turns));
-                            if entity == *player_entity {
-                                let mob_name = names.get(*mob).unwrap();
-                                let item_name = names.get(useitem.item).unwrap();
-                                gamelog.entries.push(format!(
-                                    "You use {} on {}, confusing them.",
-                                    item_name.name, mob_name.name
-                                ));
-
-                                let pos = positions.get(*mob);
-                                if let Some(pos) = pos {
-                                    particle_builder.request(
-                                        pos.x,
-                                        pos.y,
-                                        rltk::RGB::named(rltk::MAGENTA),
-                                        rltk::RGB::named(rltk::BLACK),
-                                        rltk::to_cp437('?'),
-                                        200.0,
-                                    );
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-            for mob in add_confusion.iter() {
-                confused
-                    .insert(mob.0, Confusion { turns: mob.1 })
-                    .expect("Unable to insert status");
-            }
-
-            // If its a consumable, we delete it on use
-            if used_item {
-                let consumable = consumables.get(useitem.item);
-                match consumable {
-                    None => {}
-                    Some(_) => {
-                        entities.delete(useitem.item).expect("Delete failed");
-                    }
-                }
-            }
         }
 
         wants_use.clear();
@@ -1,7 +1,7 @@
 extern crate serde;
 use rltk::{GameState, Point, Rltk};
 use specs::prelude::*;
-use specs::saveload::{SimpleMarker, SimpleMarkerAllocator};
+use specs::saveload::{MarkedBuilder, SimpleMarker};
 
 mod components;
 pub use components::*;
@@ -13,15 +13,19 @@ mod rect;
 pub use rect::Rect;
 mod visibility_system;
 use visibility_system::VisibilitySystem;
-mod monster_ai_system;
-use monster_ai_system::MonsterAI;
+mod ai;
 mod map_indexing_system;
 use map_indexing_system::MapIndexingSystem;
+mod initiative_system;
 mod melee_combat_system;
 use melee_combat_system::MeleeCombatSystem;
+mod ranged_combat_system;
+mod status_effect_system;
+use ranged_combat_system::RangedCombatSystem;
 mod damage_system;
-use damage_system::DamageSystem;
+mod effects;
 mod gamelog;
+mod gamesystem;
 mod gui;
 mod inventory_system;
 mod spawner;
@@ -29,10 +33,12 @@ use inventory_system::{ItemCollectionSystem, ItemDropSystem, ItemRemoveSystem, I
 pub mod camera;
 pub mod hunger_system;
 pub mod map_builders;
+mod mapgen;
 pub mod particle_system;
 pub mod random_table;
 pub mod raws;
 pub mod rex_assets;
+pub mod save_storage;
 pub mod saveload_system;
 pub mod trigger_system;
 #[macro_use]
@@ -40,12 +46,17 @@ extern crate lazy_static;
 
 const SHOW_MAPGEN_VISUALIZER: bool = true;
 
+#[derive(PartialEq, Copy, Clone)]
+pub enum VendorMode {
+    Buy,
+    Sell,
+}
+
 #[derive(PartialEq, Copy, Clone)]
 pub enum RunState {
     AwaitingInput,
     PreRun,
-    PlayerTurn,
-    MonsterTurn,
+    Ticking,
     ShowInventory,
     ShowDropItem,
     ShowTargeting {
@@ -56,13 +67,25 @@ pub enum RunState {
         menu_selection: gui::MainMenuSelection,
     },
     SaveGame,
+    LoadGame,
     NextLevel,
+    PreviousLevel,
     ShowRemoveItem,
     GameOver,
+    LoadFailed,
+    SaveFailed,
     MagicMapReveal {
         row: i32,
     },
     MapGeneration,
+    ShowVendor {
+        vendor: Entity,
+        mode: VendorMode,
+    },
+    ShowDialogue {
+        speaker: Entity,
+        node: usize,
+    },
 }
 
 pub struct State {
@@ -71,22 +94,45 @@ pub struct State {
     mapgen_history: Vec<Map>,
     mapgen_index: usize,
     mapgen_timer: f32,
+    saveload_error: String,
 }
 
 impl State {
     fn run_systems(&mut self) {
         let mut vis = VisibilitySystem {};
         vis.run_now(&self.ecs);
-        let mut mob = MonsterAI {};
-        mob.run_now(&self.ecs);
+        let mut initiative = initiative_system::InitiativeSystem {};
+        initiative.run_now(&self.ecs);
+        let mut hunger = hunger_system::HungerSystem {};
+        hunger.run_now(&self.ecs);
+        let mut statuses = status_effect_system::StatusEffectSystem {};
+        statuses.run_now(&self.ecs);
+        let mut quips = ai::QuipSystem {};
+        quips.run_now(&self.ecs);
+        let mut turnstatus = ai::TurnStatusSystem {};
+        turnstatus.run_now(&self.ecs);
+        let mut adjacent = ai::AdjacentAI {};
+        adjacent.run_now(&self.ecs);
+        let mut visible = ai::VisibleAI {};
+        visible.run_now(&self.ecs);
+        let mut approach = ai::ApproachAI {};
+        approach.run_now(&self.ecs);
+        let mut flee = ai::FleeAI {};
+        flee.run_now(&self.ecs);
+        let mut chase = ai::ChaseAI {};
+        chase.run_now(&self.ecs);
+        let mut search = ai::SearchAI {};
+        search.run_now(&self.ecs);
+        let mut defaultmove = ai::DefaultMoveAI {};
+        defaultmove.run_now(&self.ecs);
         let mut mapindex = MapIndexingSystem {};
         mapindex.run_now(&self.ecs);
         let mut triggers = trigger_system::TriggerSystem {};
         triggers.run_now(&self.ecs);
         let mut melee = MeleeCombatSystem {};
         melee.run_now(&self.ecs);
-        let mut damage = DamageSystem {};
-        damage.run_now(&self.ecs);
+        let mut ranged = RangedCombatSystem {};
+        ranged.run_now(&self.ecs);
         let mut pickup = ItemCollectionSystem {};
         pickup.run_now(&self.ecs);
         let mut itemuse = ItemUseSystem {};
@@ -95,8 +141,7 @@ impl State {
         drop_items.run_now(&self.ecs);
         let mut item_remove = ItemRemoveSystem {};
         item_remove.run_now(&self.ecs);
-        let mut hunger = hunger_system::HungerSystem {};
-        hunger.run_now(&self.ecs);
+        effects::run_effects_queue(&mut self.ecs);
         let mut particles = particle_system::ParticleSpawnSystem {};
         particles.run_now(&self.ecs);
 
@@ -118,6 +163,9 @@ impl GameState for State {
         match newrunstate {
             RunState::MainMenu { .. } => {}
             RunState::GameOver { .. } => {}
+            RunState::LoadGame => {}
+            RunState::LoadFailed => {}
+            RunState::SaveFailed => {}
             _ => {
                 camera::render_camera(&self.ecs, ctx);
                 gui::draw_ui(&self.ecs, ctx);
@@ -153,21 +201,21 @@ impl GameState for State {
             RunState::AwaitingInput => {
                 newrunstate = player_input(self, ctx);
             }
-            RunState::PlayerTurn => {
-                self.run_systems();
-                self.ecs.maintain();
-                match *self.ecs.fetch::<RunState>() {
-                    RunState::MagicMapReveal { .. } => {
-                        newrunstate = RunState::MagicMapReveal { row: 0 }
+            RunState::Ticking => {
+                while newrunstate == RunState::Ticking {
+                    self.run_systems();
+                    self.ecs.maintain();
+                    damage_system::delete_the_dead(&mut self.ecs);
+                    match *self.ecs.fetch::<RunState>() {
+                        RunState::AwaitingInput => newrunstate = RunState::AwaitingInput,
+                        RunState::MagicMapReveal { .. } => {
+                            newrunstate = RunState::MagicMapReveal { row: 0 }
+                        }
+                        RunState::GameOver => newrunstate = RunState::GameOver,
+                        _ => newrunstate = RunState::Ticking,
                     }
-                    _ => newrunstate = RunState::MonsterTurn,
                 }
             }
-            RunState::MonsterTurn => {
-                self.run_systems();
-                self.ecs.maintain();
-                newrunstate = RunState::AwaitingInput;
-            }
             RunState::ShowInventory => {
                 let result = gui::show_inventory(self, ctx);
                 match result.0 {
@@ -193,7 +241,7 @@ impl GameState for State {
                                     },
                                 )
                                 .expect("Unable to insert intent");
-                            newrunstate = RunState::PlayerTurn;
+                            newrunstate = RunState::Ticking;
                         }
                     }
                 }
@@ -212,7 +260,7 @@ impl GameState for State {
                                 WantsToDropItem { item: item_entity },
                             )
                             .expect("Unable to insert intent");
-                        newrunstate = RunState::PlayerTurn;
+                        newrunstate = RunState::Ticking;
                     }
                 }
             }
@@ -230,7 +278,87 @@ impl GameState for State {
                                 WantsToRemoveItem { item: item_entity },
                             )
                             .expect("Unable to insert intent");
-                        newrunstate = RunState::PlayerTurn;
+                        newrunstate = RunState::Ticking;
+                    }
+                }
+            }
+            RunState::ShowDialogue { speaker, node } => {
+                let result = gui::show_dialogue(self, ctx, speaker, node);
+                match result.0 {
+                    gui::DialogueResult::NoResponse => {}
+                    gui::DialogueResult::Done => newrunstate = RunState::AwaitingInput,
+                    gui::DialogueResult::Next => {
+                        newrunstate = RunState::ShowDialogue {
+                            speaker,
+                            node: result.1.unwrap(),
+                        }
+                    }
+                    gui::DialogueResult::Trade => {
+                        newrunstate = RunState::ShowVendor {
+                            vendor: speaker,
+                            mode: VendorMode::Buy,
+                        }
+                    }
+                }
+            }
+            RunState::ShowVendor { vendor, mode } => {
+                let result = gui::show_vendor_menu(self, ctx, vendor, mode);
+                match result.0 {
+                    gui::VendorResult::Cancel => newrunstate = RunState::AwaitingInput,
+                    gui::VendorResult::NoResponse => {}
+                    gui::VendorResult::Sell => {
+                        let price = result.3.unwrap();
+                        let player_entity = *self.ecs.fetch::<Entity>();
+                        self.ecs
+                            .write_storage::<Pools>()
+                            .get_mut(player_entity)
+                            .unwrap()
+                            .gold += price;
+                        self.ecs
+                            .delete_entity(result.1.unwrap())
+                            .expect("Unable to delete");
+                    }
+                    gui::VendorResult::Buy => {
+                        let tag = result.2.unwrap();
+                        let price = result.3.unwrap();
+                        let player_entity = *self.ecs.fetch::<Entity>();
+                        let can_afford = {
+                            let mut pools = self.ecs.write_storage::<Pools>();
+                            let player_pools = pools.get_mut(player_entity).unwrap();
+                            if player_pools.gold >= price {
+                                player_pools.gold -= price;
+                                true
+                            } else {
+                                false
+                            }
+                        };
+                        if can_afford {
+                            raws::spawn_named_item(
+                                &raws::RAWS.lock().unwrap(),
+                                self.ecs
+                                    .create_entity()
+                                    .marked::<SimpleMarker<SerializeMe>>(),
+                                &tag,
+                                raws::SpawnType::Carried { by: player_entity },
+                            );
+                        } else {
+                            self.ecs
+                                .fetch_mut::<gamelog::GameLog>()
+                                .entries
+                                .push(format!("You can't afford the {}.", tag));
+                        }
+                    }
+                    gui::VendorResult::BuyMode => {
+                        newrunstate = RunState::ShowVendor {
+                            vendor,
+                            mode: VendorMode::Buy,
+                        }
+                    }
+                    gui::VendorResult::SellMode => {
+                        newrunstate = RunState::ShowVendor {
+                            vendor,
+                            mode: VendorMode::Sell,
+                        }
                     }
                 }
             }
@@ -250,7 +378,7 @@ impl GameState for State {
                                 },
                             )
                             .expect("Unable to insert intent");
-                        newrunstate = RunState::PlayerTurn;
+                        newrunstate = RunState::Ticking;
                     }
                 }
             }
@@ -264,11 +392,7 @@ impl GameState for State {
                     }
                     gui::MainMenuResult::Selected { selected } => match selected {
                         gui::MainMenuSelection::NewGame => newrunstate = RunState::PreRun,
-                        gui::MainMenuSelection::LoadGame => {
-                            saveload_system::load_game(&mut self.ecs);
-                            newrunstate = RunState::AwaitingInput;
-                            saveload_system::delete_save();
-                        }
+                        gui::MainMenuSelection::LoadGame => newrunstate = RunState::LoadGame,
                         gui::MainMenuSelection::Quit => {
                             ::std::process::exit(0);
                         }
@@ -276,7 +400,20 @@ impl GameState for State {
                 }
             }
             RunState::GameOver => {
-                let result = gui::game_over(ctx);
+                let result = gui::game_over(&self.ecs, ctx);
+                match result {
+                    gui::GameOverResult::NoSelection => {}
+                    gui::GameOverResult::QuitToMenu => {
+                        self.game_over_cleanup();
+                        newrunstate = RunState::MapGeneration;
+                        self.mapgen_next_state = Some(RunState::MainMenu {
+                            menu_selection: gui::MainMenuSelection::NewGame,
+                        });
+                    }
+                }
+            }
+            RunState::LoadFailed => {
+                let result = gui::load_failed(&self.saveload_error, ctx);
                 match result {
                     gui::GameOverResult::NoSelection => {}
                     gui::GameOverResult::QuitToMenu => {
@@ -288,14 +425,60 @@ impl GameState for State {
                     }
                 }
             }
+            RunState::SaveFailed => {
+                let result = gui::save_failed(&self.saveload_error, ctx);
+                match result {
+                    gui::GameOverResult::NoSelection => {}
+                    gui::GameOverResult::QuitToMenu => newrunstate = RunState::AwaitingInput,
+                }
+            }
             RunState::SaveGame => {
-                saveload_system::save_game(&mut self.ecs);
-                newrunstate = RunState::MainMenu {
-                    menu_selection: gui::MainMenuSelection::LoadGame,
-                };
+                let (result, slot) = gui::save_slot_menu(ctx, true);
+                match result {
+                    gui::SaveSlotResult::NoResponse => {}
+                    gui::SaveSlotResult::Cancel => newrunstate = RunState::AwaitingInput,
+                    gui::SaveSlotResult::Selected => {
+                        match saveload_system::save_game(&mut self.ecs, slot.unwrap()) {
+                            Ok(()) => {
+                                newrunstate = RunState::MainMenu {
+                                    menu_selection: gui::MainMenuSelection::LoadGame,
+                                };
+                            }
+                            Err(e) => {
+                                self.saveload_error = e;
+                                newrunstate = RunState::SaveFailed;
+                            }
+                        }
+                    }
+                }
+            }
+            RunState::LoadGame => {
+                let (result, slot) = gui::save_slot_menu(ctx, false);
+                match result {
+                    gui::SaveSlotResult::NoResponse => {}
+                    gui::SaveSlotResult::Cancel => {
+                        newrunstate = RunState::MainMenu {
+                            menu_selection: gui::MainMenuSelection::LoadGame,
+                        }
+                    }
+                    gui::SaveSlotResult::Selected => {
+                        match saveload_system::load_game(&mut self.ecs, slot.unwrap()) {
+                            Ok(()) => newrunstate = RunState::AwaitingInput,
+                            Err(e) => {
+                                self.saveload_error = e;
+                                newrunstate = RunState::LoadFailed;
+                            }
+                        }
+                    }
+                }
             }
             RunState::NextLevel => {
-                self.goto_next_level();
+                self.goto_level(1);
+                self.mapgen_next_state = Some(RunState::PreRun);
+                newrunstate = RunState::MapGeneration;
+            }
+            RunState::PreviousLevel => {
+                self.goto_level(-1);
                 self.mapgen_next_state = Some(RunState::PreRun);
                 newrunstate = RunState::MapGeneration;
             }
@@ -306,7 +489,7 @@ impl GameState for State {
                     map.revealed_tiles[idx] = true;
                 }
                 if row == map.height - 1 {
-                    newrunstate = RunState::MonsterTurn;
+                    newrunstate = RunState::Ticking;
                 } else {
                     newrunstate = RunState::MagicMapReveal { row: row + 1 };
                 }
@@ -322,77 +505,40 @@ impl GameState for State {
 }
 
 impl State {
-    fn entities_to_remove_on_level_change(&mut self) -> Vec<Entity> {
-        let entities = self.ecs.entities();
-        let player = self.ecs.read_storage::<Player>();
-        let backpack = self.ecs.read_storage::<InBackpack>();
-        let player_entity = self.ecs.fetch::<Entity>();
-        let equipped = self.ecs.read_storage::<Equipped>();
-
-        let mut to_delete: Vec<Entity> = Vec::new();
-        for entity in entities.join() {
-            let mut should_delete = true;
-
-            // Don't delete the player
-            let p = player.get(entity);
-            if let Some(_p) = p {
-                should_delete = false;
-            }
-
-            // Don't delete the player's equipment
-            let bp = backpack.get(entity);
-            if let Some(bp) = bp {
-                if bp.owner == *player_entity {
-                    should_delete = false;
-                }
-            }
-
-            let eq = equipped.get(entity);
-            if let Some(eq) = eq {
-                if eq.owner == *player_entity {
-                    should_delete = false;
-                }
-            }
-
-            if should_delete {
-                to_delete.push(entity);
-            }
-        }
-
-        to_delete
-    }
-
-    fn goto_next_level(&mut self) {
-        // Delete entities that aren't the player or his/her equipment
-        let to_delete = self.entities_to_remove_on_level_change();
-        for target in to_delete {
-            self.ecs
-                .delete_entity(target)
-                .expect("Unable to delete entity");
-        }
+    fn goto_level(&mut self, offset: i32) {
+        freeze_level_entities(&mut self.ecs);
 
         // Build a new map and place the player
-        let current_depth;
-        {
-            let worldmap_resource = self.ecs.fetch::<Map>();
-            current_depth = worldmap_resource.depth;
-        }
-        self.generate_world_map(current_depth + 1);
+        let current_depth = self.ecs.fetch::<Map>().depth;
+        self.generate_world_map(current_depth + offset, offset);
 
-        // Notify the player and give them some health
-        let player_entity = self.ecs.fetch::<Entity>();
+        // Notify the player
         let mut gamelog = self.ecs.fetch_mut::<gamelog::GameLog>();
-        gamelog
-            .entries
-            .push("You descend to the next level, and take a moment to heal.".to_string());
-        let mut player_health_store = self.ecs.write_storage::<CombatStats>();
-        let player_health = player_health_store.get_mut(*player_entity);
-        if let Some(player_health) = player_health {
-            player_health.hp = i32::max(player_health.hp, player_health.max_hp / 2);
+        if offset > 0 {
+            gamelog
+                .entries
+                .push("You descend to the next level, and take a moment to heal.".to_string());
+
+            // Give them some health
+            let player_entity = self.ecs.fetch::<Entity>();
+            let mut player_health_store = self.ecs.write_storage::<Pools>();
+            let player_health = player_health_store.get_mut(*player_entity);
+            if let Some(player_health) = player_health {
+                player_health.hit_points.current = i32::max(
+                    player_health.hit_points.current,
+                    player_health.hit_points.max / 2,
+                );
+            }
+        } else {
+            gamelog
+                .entries
+                .push("You climb back up to the previous level.".to_string());
         }
     }
 
     fn game_over_cleanup(&mut self) {
+        effects::clear_effects();
+
         // Delete everything
         let mut to_delete = Vec::new();
         for e in self.ecs.entities().join() {
@@ -409,57 +555,53 @@ impl State {
             *player_entity_writer = player_entity;
         }
 
+        // Start a fresh run with a new seed
+        let seed = rltk::RandomNumberGenerator::new().next_u64();
+        self.ecs.insert(MasterDungeonMap::new(seed));
+        self.ecs.insert(rltk::RandomNumberGenerator::seeded(seed));
+
         // Build a new map and place the player
-        self.generate_world_map(1);
+        self.generate_world_map(1, 0);
     }
 
-    fn generate_world_map(&mut self, new_depth: i32) {
+    fn generate_world_map(&mut self, new_depth: i32, offset: i32) {
         self.mapgen_index = 0;
         self.mapgen_timer = 0.0;
         self.mapgen_history.clear();
-        let mut rng = self.ecs.write_resource::<rltk::RandomNumberGenerator>();
-        let mut builder = map_builders::level_builder(new_depth, &mut rng, 80, 50);
-        builder.build_map(&mut rng);
-        self.mapgen_history = builder.build_data.history.clone();
-        let player_start;
-        {
-            let mut worldmap_resource = self.ecs.write_resource::<Map>();
-            *worldmap_resource = builder.build_data.map.clone();
-            player_start = builder
-                .build_data
-                .starting_position
-                .as_mut()
-                .unwrap()
-                .clone();
-        }
-
-        // Spawn bad guys
-        std::mem::drop(rng);
-        builder.spawn_entities(&mut self.ecs);
-
-        // Place the player and update resources
-        let (player_x, player_y) = (player_start.x, player_start.y);
-        let mut player_position = self.ecs.write_resource::<Point>();
-        *player_position = Point::new(player_x, player_y);
-        let mut position_components = self.ecs.write_storage::<Position>();
-        let player_entity = self.ecs.fetch::<Entity>();
-        let player_pos_comp = position_components.get_mut(*player_entity);
-        if let Some(player_pos_comp) = player_pos_comp {
-            player_pos_comp.x = player_x;
-            player_pos_comp.y = player_y;
+        let map_building_info = level_transition(&mut self.ecs, new_depth, offset);
+        if let Some(history) = map_building_info {
+            self.mapgen_history = history;
+        } else {
+            thaw_level_entities(&mut self.ecs);
         }
+    }
+}
 
-        // Mark the player's visibility as dirty
-        let mut viewshed_components = self.ecs.write_storage::<Viewshed>();
-        let vs = viewshed_components.get_mut(*player_entity);
-        if let Some(vs) = vs {
-            vs.dirty = true;
+/// Reads an optional `--seed <number>` from the command line, so a run can be replayed.
+fn seed_from_args() -> Result<Option<u64>, String> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--seed" {
+            let value = args.next().ok_or("--seed requires a number")?;
+            return value
+                .parse::<u64>()
+                .map(Some)
+                .map_err(|_| format!("Invalid seed: {}", value));
         }
     }
+    Ok(None)
 }
 
 fn main() -> rltk::BError {
     use rltk::RltkBuilder;
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() > 1 && args[1] == "mapgen" {
+        raws::load_raws();
+        return mapgen::run(&args[2..]).map_err(|e| e.into());
+    }
+
+    let seed = seed_from_args()?.unwrap_or_else(|| rltk::RandomNumberGenerator::new().next_u64());
+
     let context = RltkBuilder::simple80x50()
         .with_title("Roguelike Tutorial")
         .build()?;
@@ -472,53 +614,17 @@ fn main() -> rltk::BError {
         mapgen_index: 0,
         mapgen_history: Vec::new(),
         mapgen_timer: 0.0,
+        saveload_error: String::new(),
     };
-    gs.ecs.register::<Position>();
-    gs.ecs.register::<Renderable>();
-    gs.ecs.register::<Player>();
-    gs.ecs.register::<Viewshed>();
-    gs.ecs.register::<Monster>();
-    gs.ecs.register::<Name>();
-    gs.ecs.register::<BlocksTile>();
-    gs.ecs.register::<CombatStats>();
-    gs.ecs.register::<WantsToMelee>();
-    gs.ecs.register::<SufferDamage>();
-    gs.ecs.register::<Item>();
-    gs.ecs.register::<ProvidesHealing>();
-    gs.ecs.register::<InflictsDamage>();
-    gs.ecs.register::<AreaOfEffect>();
-    gs.ecs.register::<Consumable>();
-    gs.ecs.register::<Ranged>();
-    gs.ecs.register::<InBackpack>();
-    gs.ecs.register::<WantsToPickupItem>();
-    gs.ecs.register::<WantsToUseItem>();
-    gs.ecs.register::<WantsToDropItem>();
-    gs.ecs.register::<Confusion>();
-    gs.ecs.register::<SimpleMarker<SerializeMe>>();
-    gs.ecs.register::<SerializationHelper>();
-    gs.ecs.register::<Equippable>();
-    gs.ecs.register::<Equipped>();
-    gs.ecs.register::<MeleePowerBonus>();
-    gs.ecs.register::<DefenseBonus>();
-    gs.ecs.register::<WantsToRemoveItem>();
-    gs.ecs.register::<ParticleLifetime>();
-    gs.ecs.register::<HungerClock>();
-    gs.ecs.register::<ProvidesFood>();
-    gs.ecs.register::<MagicMapper>();
-    gs.ecs.register::<Hidden>();
-    gs.ecs.register::<EntryTrigger>();
-    gs.ecs.register::<EntityMoved>();
-    gs.ecs.register::<SingleActivation>();
-    gs.ecs.register::<BlocksVisibility>();
-    gs.ecs.register::<Door>();
-    gs.ecs.register::<Bystander>();
-    gs.ecs.insert(SimpleMarkerAllocator::<SerializeMe>::new());
+    saveload_system::register_components(&mut gs.ecs);
+    saveload_system::import_legacy_save();
 
     raws::load_raws();
 
+    gs.ecs.insert(MasterDungeonMap::new(seed));
     gs.ecs.insert(Map::new(1, 64, 64));
     gs.ecs.insert(Point::new(0, 0));
-    gs.ecs.insert(rltk::RandomNumberGenerator::new());
+    gs.ecs.insert(rltk::RandomNumberGenerator::seeded(seed));
     let player_entity = spawner::player(&mut gs.ecs, 0, 0);
     gs.ecs.insert(player_entity);
     gs.ecs.insert(RunState::MapGeneration {});
@@ -528,7 +634,7 @@ fn main() -> rltk::BError {
     gs.ecs.insert(particle_system::ParticleBuilder::new());
     gs.ecs.insert(rex_assets::RexAssets::new());
 
-    gs.generate_world_map(1);
+    gs.generate_world_map(1, 0);
 
     rltk::main_loop(context, gs)
 }
@@ -0,0 +1,207 @@
+use super::{Map, TileType};
+use crate::components::{OtherLevelPosition, Player, Position, Viewshed};
+use crate::map_builders::dungeon_level;
+use rltk::Point;
+use serde::{Deserialize, Serialize};
+use specs::prelude::*;
+use std::collections::HashMap;
+
+/// Keeps a copy of every level the player has visited, keyed by depth, along with the seed
+/// the run's levels are generated from and how many turns the player has taken.
+#[derive(Default, Serialize, Deserialize, Clone)]
+pub struct MasterDungeonMap {
+    maps: HashMap<i32, Map>,
+    pub seed: u64,
+    #[serde(default)]
+    pub turns: u64,
+}
+
+impl MasterDungeonMap {
+    pub fn new(seed: u64) -> MasterDungeonMap {
+        MasterDungeonMap {
+            maps: HashMap::new(),
+            seed,
+            turns: 0,
+        }
+    }
+
+    /// Derives the seed for a single depth from the run seed (a SplitMix64 step), so each level
+    /// can be regenerated on its own without replaying the levels above it.
+    pub fn depth_seed(&self, depth: i32) -> u64 {
+        let mut z = self
+            .seed
+            .wrapping_add((depth as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    pub fn store_map(&mut self, map: &Map) {
+        self.maps.insert(map.depth, map.clone());
+    }
+
+    pub fn get_map(&self, depth: i32) -> Option<Map> {
+        if self.maps.contains_key(&depth) {
+            let mut result = self.maps[&depth].clone();
+            result.tile_content = vec![Vec::new(); (result.width * result.height) as usize];
+            Some(result)
+        } else {
+            None
+        }
+    }
+}
+
+/// Moves the player to `new_depth`. If the level has been visited before it is restored from
+/// the master map and `None` is returned; otherwise a new level is built and its generation
+/// history is returned for the visualizer.
+pub fn level_transition(ecs: &mut World, new_depth: i32, offset: i32) -> Option<Vec<Map>> {
+    // Obtain the master dungeon map
+    let dungeon_master = ecs.read_resource::<MasterDungeonMap>();
+
+    // Do we already have a map?
+    if dungeon_master.get_map(new_depth).is_some() {
+        std::mem::drop(dungeon_master);
+        load_existing_map(ecs, new_depth, offset);
+        None
+    } else {
+        std::mem::drop(dungeon_master);
+        Some(transition_to_new_map(ecs, new_depth))
+    }
+}
+
+fn transition_to_new_map(ecs: &mut World, new_depth: i32) -> Vec<Map> {
+    let seed = ecs.fetch::<MasterDungeonMap>().depth_seed(new_depth);
+    let mut builder = dungeon_level(new_depth, seed, 80, 50, crate::SHOW_MAPGEN_VISUALIZER);
+    let mapgen_history = builder.build_data.history.clone();
+    let player_start;
+    {
+        let mut worldmap_resource = ecs.write_resource::<Map>();
+        *worldmap_resource = builder.build_data.map.clone();
+        player_start = builder
+            .build_data
+            .starting_position
+            .as_mut()
+            .unwrap()
+            .clone();
+    }
+
+    // Spawn bad guys
+    builder.spawn_entities(ecs);
+
+    // Place the player and update resources
+    place_player(ecs, player_start.x, player_start.y);
+
+    // Store the newly minted map
+    let mut dungeon_master = ecs.write_resource::<MasterDungeonMap>();
+    dungeon_master.store_map(&builder.build_data.map);
+
+    mapgen_history
+}
+
+fn load_existing_map(ecs: &mut World, new_depth: i32, offset: i32) {
+    let dungeon_master = ecs.read_resource::<MasterDungeonMap>();
+    let map = dungeon_master.get_map(new_depth).unwrap();
+    std::mem::drop(dungeon_master);
+
+    // Arrive on the staircase that leads back where we came from
+    let stair_type = if offset < 0 {
+        TileType::DownStairs
+    } else {
+        TileType::UpStairs
+    };
+    let w = map.width;
+    let arrival = map
+        .tiles
+        .iter()
+        .position(|tt| *tt == stair_type)
+        .map(|idx| (idx as i32 % w, idx as i32 / w));
+
+    *ecs.write_resource::<Map>() = map;
+
+    if let Some((x, y)) = arrival {
+        place_player(ecs, x, y);
+    }
+}
+
+fn place_player(ecs: &mut World, x: i32, y: i32) {
+    let mut player_position = ecs.write_resource::<Point>();
+    *player_position = Point::new(x, y);
+    let mut position_components = ecs.write_storage::<Position>();
+    let player_entity = ecs.fetch::<Entity>();
+    let player_pos_comp = position_components.get_mut(*player_entity);
+    if let Some(player_pos_comp) = player_pos_comp {
+        player_pos_comp.x = x;
+        player_pos_comp.y = y;
+    }
+
+    // Mark the player's visibility as dirty
+    let mut viewshed_components = ecs.write_storage::<Viewshed>();
+    let vs = viewshed_components.get_mut(*player_entity);
+    if let Some(vs) = vs {
+        vs.dirty = true;
+    }
+}
+
+/// Stores the current map and parks every positioned entity (other than the player) on it,
+/// so they disappear from the world until the player returns to this depth.
+pub fn freeze_level_entities(ecs: &mut World) {
+    // Remember the map as the player leaves it
+    {
+        let map = ecs.fetch::<Map>();
+        let mut dungeon_master = ecs.write_resource::<MasterDungeonMap>();
+        dungeon_master.store_map(&map);
+    }
+
+    // Obtain ECS access
+    let entities = ecs.entities();
+    let mut positions = ecs.write_storage::<Position>();
+    let mut other_level_positions = ecs.write_storage::<OtherLevelPosition>();
+    let players = ecs.read_storage::<Player>();
+    let map_depth = ecs.fetch::<Map>().depth;
+
+    // Find positions and make OtherLevelPosition
+    let mut pos_to_delete: Vec<Entity> = Vec::new();
+    for (entity, pos, _player) in (&entities, &positions, !&players).join() {
+        other_level_positions
+            .insert(
+                entity,
+                OtherLevelPosition {
+                    x: pos.x,
+                    y: pos.y,
+                    depth: map_depth,
+                },
+            )
+            .expect("Insert fail");
+        pos_to_delete.push(entity);
+    }
+
+    // Remove positions
+    for p in pos_to_delete.iter() {
+        positions.remove(*p);
+    }
+}
+
+/// Brings back every entity that was frozen on the current map's depth.
+pub fn thaw_level_entities(ecs: &mut World) {
+    // Obtain ECS access
+    let entities = ecs.entities();
+    let mut positions = ecs.write_storage::<Position>();
+    let mut other_level_positions = ecs.write_storage::<OtherLevelPosition>();
+    let map_depth = ecs.fetch::<Map>().depth;
+
+    // Find OtherLevelPosition
+    let mut pos_to_delete: Vec<Entity> = Vec::new();
+    for (entity, pos) in (&entities, &other_level_positions).join() {
+        if pos.depth == map_depth {
+            positions
+                .insert(entity, Position { x: pos.x, y: pos.y })
+                .expect("Insert fail");
+            pos_to_delete.push(entity);
+        }
+    }
+
+    // Remove OtherLevelPosition
+    for p in pos_to_delete.iter() {
+        other_level_positions.remove(*p);
+    }
+}
@@ -1,5 +1,7 @@
+mod dungeon;
 mod tiletype;
 
+pub use dungeon::*;
 use rltk::{Algorithm2D, BaseMap, Point};
 use serde::{Deserialize, Serialize};
 use specs::prelude::*;
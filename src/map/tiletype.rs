@@ -5,6 +5,7 @@ pub enum TileType {
     Wall,
     Floor,
     DownStairs,
+    UpStairs,
     Road,
     Grass,
     Gravel,
@@ -18,6 +19,7 @@ pub fn tile_walkable(tt: TileType) -> bool {
     match tt {
         TileType::Floor
         | TileType::DownStairs
+        | TileType::UpStairs
         | TileType::Road
         | TileType::Grass
         | TileType::Gravel
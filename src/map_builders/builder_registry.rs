@@ -0,0 +1,348 @@
+use super::area_starting_points::{AreaStartingPosition, XStart, YStart};
+use super::bsp_dungeon::BspDungeonBuilder;
+use super::bsp_interior::BspInteriorBuilder;
+use super::cellular_automata::CellularAutomataBuilder;
+use super::cull_unreachable::CullUnreachable;
+use super::distant_exit::DistantExit;
+use super::dla::{DLAAlgorithm, DLABuilder};
+use super::door_placement::DoorPlacement;
+use super::drunkard::{DrunkSpawnMode, DrunkardSettings, DrunkardsWalkBuilder};
+use super::maze::MazeBuilder;
+use super::prefab_builder::{prefab_levels, prefab_sections, PrefabBuilder};
+use super::room_based_spawner::RoomBasedSpawner;
+use super::room_based_stairs::RoomBasedStairs;
+use super::room_based_starting_position::RoomBasedStartingPosition;
+use super::room_corner_rounding::RoomCornerRounder;
+use super::room_corridor_spawner::CorridorSpawner;
+use super::room_draw::RoomDrawer;
+use super::room_exploder::RoomExploder;
+use super::room_sorter::{RoomSort, RoomSorter};
+use super::rooms_corridors_bsp::BspCorridors;
+use super::rooms_corridors_dogleg::DoglegCorridors;
+use super::rooms_corridors_lines::StraightLineCorridors;
+use super::rooms_corridors_nearest::NearestCorridors;
+use super::simple_map::SimpleMapBuilder;
+use super::town::TownBuilder;
+use super::voronoi::{DistanceAlgorithm, VoronoiCellBuilder};
+use super::voronoi_spawning::VoronoiSpawning;
+use super::waveform_collapse::WaveformCollapseBuilder;
+use super::{BuilderChain, InitialMapBuilder, MetaMapBuilder, Symmetry};
+use crate::raws::{BuilderStep, LevelChain};
+use rltk::RandomNumberGenerator;
+use std::collections::HashMap;
+
+type InitialFactory =
+    fn(&BuilderStep, &mut RandomNumberGenerator) -> Result<Box<dyn InitialMapBuilder>, String>;
+type MetaFactory =
+    fn(&BuilderStep, &mut RandomNumberGenerator) -> Result<Box<dyn MetaMapBuilder>, String>;
+
+/// Maps the builder names used in `raws/levels.json` to the code that constructs them.
+pub struct BuilderRegistry {
+    initial: HashMap<&'static str, InitialFactory>,
+    meta: HashMap<&'static str, MetaFactory>,
+}
+
+impl BuilderRegistry {
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> BuilderRegistry {
+        let mut registry = BuilderRegistry {
+            initial: HashMap::new(),
+            meta: HashMap::new(),
+        };
+
+        // Starting builders
+        registry.initial("town", |_, _| Ok(TownBuilder::new()));
+        registry.initial("simple_map", |_, _| Ok(SimpleMapBuilder::new()));
+        registry.initial("bsp_dungeon", |_, _| Ok(BspDungeonBuilder::new()));
+        registry.initial("bsp_interior", |_, _| Ok(BspInteriorBuilder::new()));
+        registry.initial("cellular_automata", |_, _| {
+            Ok(CellularAutomataBuilder::new())
+        });
+        registry.initial("maze", |_, _| Ok(MazeBuilder::new()));
+        registry.initial("drunkards_walk", |step, _| Ok(drunkards_walk(step)?));
+        registry.initial("dla", |step, _| Ok(dla(step)?));
+        registry.initial("voronoi", |step, _| {
+            let algorithm = match step.algorithm.as_deref().unwrap_or("pythagoras") {
+                "pythagoras" => DistanceAlgorithm::Pythagoras,
+                "manhattan" => DistanceAlgorithm::Manhattan,
+                "chebyshev" => DistanceAlgorithm::Chebyshev,
+                other => return Err(format!("unknown distance algorithm '{}'", other)),
+            };
+            Ok(VoronoiCellBuilder::custom(
+                step.seeds.unwrap_or(64),
+                algorithm,
+            ))
+        });
+        registry.initial("prefab_level", |step, _| match step.template.as_deref() {
+            Some("wfc_populated") => Ok(PrefabBuilder::constant(prefab_levels::WFC_POPULATED)),
+            other => Err(format!("unknown prefab level {:?}", other)),
+        });
+
+        // Meta builders
+        registry.meta("cellular_automata", |_, _| {
+            Ok(CellularAutomataBuilder::new())
+        });
+        registry.meta("drunkards_walk", |step, _| Ok(drunkards_walk(step)?));
+        registry.meta("dla", |step, _| Ok(dla(step)?));
+        registry.meta("room_sorter", |step, _| {
+            let sort = match step.sort.as_deref().unwrap_or("leftmost") {
+                "leftmost" => RoomSort::LEFTMOST,
+                "rightmost" => RoomSort::RIGHTMOST,
+                "topmost" => RoomSort::TOPMOST,
+                "bottommost" => RoomSort::BOTTOMMOST,
+                "central" => RoomSort::CENTRAL,
+                other => return Err(format!("unknown room sort '{}'", other)),
+            };
+            Ok(RoomSorter::new(sort))
+        });
+        registry.meta("room_draw", |_, _| Ok(RoomDrawer::new()));
+        registry.meta("corridors_dogleg", |_, _| Ok(DoglegCorridors::new()));
+        registry.meta("corridors_nearest", |_, _| Ok(NearestCorridors::new()));
+        registry.meta("corridors_lines", |_, _| Ok(StraightLineCorridors::new()));
+        registry.meta("corridors_bsp", |_, _| Ok(BspCorridors::new()));
+        registry.meta("corridor_spawner", |_, _| Ok(CorridorSpawner::new()));
+        registry.meta("room_exploder", |_, _| Ok(RoomExploder::new()));
+        registry.meta("room_corner_rounder", |_, _| Ok(RoomCornerRounder::new()));
+        registry.meta("room_based_starting_position", |_, _| {
+            Ok(RoomBasedStartingPosition::new())
+        });
+        registry.meta("area_starting_position", |step, rng| {
+            let x = match step.x_start.as_deref().unwrap_or("center") {
+                "left" => XStart::LEFT,
+                "center" => XStart::CENTER,
+                "right" => XStart::RIGHT,
+                "random" => match rng.roll_dice(1, 3) {
+                    1 => XStart::LEFT,
+                    2 => XStart::CENTER,
+                    _ => XStart::RIGHT,
+                },
+                other => return Err(format!("unknown x_start '{}'", other)),
+            };
+            let y = match step.y_start.as_deref().unwrap_or("center") {
+                "top" => YStart::TOP,
+                "center" => YStart::CENTER,
+                "bottom" => YStart::BOTTOM,
+                "random" => match rng.roll_dice(1, 3) {
+                    1 => YStart::BOTTOM,
+                    2 => YStart::CENTER,
+                    _ => YStart::TOP,
+                },
+                other => return Err(format!("unknown y_start '{}'", other)),
+            };
+            Ok(AreaStartingPosition::new(x, y))
+        });
+        registry.meta("room_based_stairs", |_, _| Ok(RoomBasedStairs::new()));
+        registry.meta("distant_exit", |_, _| Ok(DistantExit::new()));
+        registry.meta("room_based_spawner", |_, _| Ok(RoomBasedSpawner::new()));
+        registry.meta("voronoi_spawning", |_, _| Ok(VoronoiSpawning::new()));
+        registry.meta("cull_unreachable", |_, _| Ok(CullUnreachable::new()));
+        registry.meta("waveform_collapse", |_, _| {
+            Ok(WaveformCollapseBuilder::new())
+        });
+        registry.meta("door_placement", |_, _| Ok(DoorPlacement::new()));
+        registry.meta("prefab_vaults", |_, _| Ok(PrefabBuilder::vaults()));
+        registry.meta("prefab_section", |step, _| match step.template.as_deref() {
+            Some("underground_fort") => {
+                Ok(PrefabBuilder::sectional(prefab_sections::UNDERGROUND_FORT))
+            }
+            other => Err(format!("unknown prefab section {:?}", other)),
+        });
+
+        registry
+    }
+
+    fn initial(&mut self, name: &'static str, factory: InitialFactory) {
+        self.initial.insert(name, factory);
+    }
+
+    fn meta(&mut self, name: &'static str, factory: MetaFactory) {
+        self.meta.insert(name, factory);
+    }
+
+    /// Turns a chain description into a ready-to-run `BuilderChain`. Random choices in the
+    /// description (`one_of`, `chance`, random start positions) are made with `rng`.
+    pub fn build_chain(
+        &self,
+        chain: &LevelChain,
+        new_depth: i32,
+        rng: &mut RandomNumberGenerator,
+        width: i32,
+        height: i32,
+    ) -> Result<BuilderChain, String> {
+        let mut builder = BuilderChain::new(new_depth, rng.next_u64(), width, height);
+
+        let mut starters = Vec::new();
+        resolve_step(&chain.starter, rng, &mut starters)?;
+        if starters.len() != 1 {
+            return Err(format!(
+                "chain '{}' must start with exactly one builder",
+                chain.name
+            ));
+        }
+        builder.start_with(self.make_initial(starters[0], rng)?);
+
+        for step in chain.builders.iter() {
+            let mut resolved = Vec::new();
+            resolve_step(step, rng, &mut resolved)?;
+            for meta_step in resolved {
+                builder.with(self.make_meta(meta_step, rng)?);
+            }
+        }
+
+        Ok(builder)
+    }
+
+    /// Checks every alternative in a chain description, so mistakes in the raws are reported
+    /// when they are loaded rather than when an unlucky roll reaches them.
+    pub fn validate_chain(&self, chain: &LevelChain) -> Vec<String> {
+        let mut errors = Vec::new();
+        let mut rng = RandomNumberGenerator::seeded(0);
+        self.validate_step(&chain.starter, true, &mut rng, &mut errors);
+        for step in chain.builders.iter() {
+            self.validate_step(step, false, &mut rng, &mut errors);
+        }
+        errors
+    }
+
+    fn validate_step(
+        &self,
+        step: &BuilderStep,
+        initial: bool,
+        rng: &mut RandomNumberGenerator,
+        errors: &mut Vec<String>,
+    ) {
+        if initial && step.sequence.is_some() {
+            errors.push("a chain's starter can't be a sequence".to_string());
+        }
+        if let Some(options) = step.one_of.as_ref().or(step.sequence.as_ref()) {
+            for option in options.iter() {
+                self.validate_step(option, initial, rng, errors);
+            }
+        } else {
+            let result = if initial {
+                self.make_initial(step, rng).map(|_| ())
+            } else {
+                self.make_meta(step, rng).map(|_| ())
+            };
+            if let Err(e) = result {
+                errors.push(e);
+            }
+        }
+    }
+
+    fn make_initial(
+        &self,
+        step: &BuilderStep,
+        rng: &mut RandomNumberGenerator,
+    ) -> Result<Box<dyn InitialMapBuilder>, String> {
+        let name = step_name(step)?;
+        match self.initial.get(name) {
+            Some(factory) => factory(step, rng).map_err(|e| format!("{}: {}", name, e)),
+            None => Err(format!("'{}' is not a starting builder", name)),
+        }
+    }
+
+    fn make_meta(
+        &self,
+        step: &BuilderStep,
+        rng: &mut RandomNumberGenerator,
+    ) -> Result<Box<dyn MetaMapBuilder>, String> {
+        let name = step_name(step)?;
+        match self.meta.get(name) {
+            Some(factory) => factory(step, rng).map_err(|e| format!("{}: {}", name, e)),
+            None => Err(format!("'{}' is not a meta builder", name)),
+        }
+    }
+}
+
+fn step_name(step: &BuilderStep) -> Result<&str, String> {
+    step.name
+        .as_deref()
+        .ok_or_else(|| "step has no builder name, one_of or sequence".to_string())
+}
+
+/// Flattens a step into the builders it stands for, rolling `chance` and `one_of` as it goes.
+fn resolve_step<'a>(
+    step: &'a BuilderStep,
+    rng: &mut RandomNumberGenerator,
+    resolved: &mut Vec<&'a BuilderStep>,
+) -> Result<(), String> {
+    if let Some(chance) = step.chance {
+        if rng.rand::<f32>() >= chance {
+            return Ok(());
+        }
+    }
+
+    if let Some(options) = &step.one_of {
+        let total_weight: i32 = options.iter().map(|o| o.weight.unwrap_or(1)).sum();
+        if total_weight < 1 {
+            return Err("one_of needs at least one weighted option".to_string());
+        }
+        let mut roll = rng.roll_dice(1, total_weight) - 1;
+        for option in options.iter() {
+            let weight = option.weight.unwrap_or(1);
+            if roll < weight {
+                return resolve_step(option, rng, resolved);
+            }
+            roll -= weight;
+        }
+    } else if let Some(steps) = &step.sequence {
+        for sub_step in steps.iter() {
+            resolve_step(sub_step, rng, resolved)?;
+        }
+    } else {
+        resolved.push(step);
+    }
+    Ok(())
+}
+
+fn parse_symmetry(step: &BuilderStep) -> Result<Symmetry, String> {
+    match step.symmetry.as_deref().unwrap_or("none") {
+        "none" => Ok(Symmetry::None),
+        "horizontal" => Ok(Symmetry::Horizontal),
+        "vertical" => Ok(Symmetry::Vertical),
+        "both" => Ok(Symmetry::Both),
+        other => Err(format!("unknown symmetry '{}'", other)),
+    }
+}
+
+fn drunkards_walk(step: &BuilderStep) -> Result<Box<DrunkardsWalkBuilder>, String> {
+    if let Some(preset) = &step.preset {
+        return match preset.as_str() {
+            "open_area" => Ok(DrunkardsWalkBuilder::open_area()),
+            "open_halls" => Ok(DrunkardsWalkBuilder::open_halls()),
+            "winding_passages" => Ok(DrunkardsWalkBuilder::winding_passages()),
+            "fat_passages" => Ok(DrunkardsWalkBuilder::fat_passages()),
+            "fearful_symmetry" => Ok(DrunkardsWalkBuilder::fearful_symmetry()),
+            other => Err(format!("unknown preset '{}'", other)),
+        };
+    }
+
+    let spawn_mode = match step.spawn_mode.as_deref().unwrap_or("starting_point") {
+        "starting_point" => DrunkSpawnMode::StartingPoint,
+        "random" => DrunkSpawnMode::Random,
+        other => return Err(format!("unknown spawn mode '{}'", other)),
+    };
+    Ok(Box::new(DrunkardsWalkBuilder::new(DrunkardSettings {
+        spawn_mode,
+        drunken_lifetime: step.lifetime.unwrap_or(400),
+        floor_percent: step.floor_percent.unwrap_or(0.5),
+        brush_size: step.brush_size.unwrap_or(1),
+        symmetry: parse_symmetry(step)?,
+    })))
+}
+
+fn dla(step: &BuilderStep) -> Result<Box<DLABuilder>, String> {
+    let algorithm = match step.algorithm.as_deref().unwrap_or("walk_inwards") {
+        "walk_inwards" => DLAAlgorithm::WalkInwards,
+        "walk_outwards" => DLAAlgorithm::WalkOutwards,
+        "central_attractor" => DLAAlgorithm::CentralAttractor,
+        other => return Err(format!("unknown algorithm '{}'", other)),
+    };
+    Ok(DLABuilder::custom(
+        algorithm,
+        step.brush_size.unwrap_or(2),
+        parse_symmetry(step)?,
+        step.floor_percent.unwrap_or(0.25),
+    ))
+}
@@ -41,6 +41,21 @@ impl DLABuilder {
         })
     }
 
+    #[allow(dead_code)]
+    pub fn custom(
+        algorithm: DLAAlgorithm,
+        brush_size: i32,
+        symmetry: Symmetry,
+        floor_percent: f32,
+    ) -> Box<DLABuilder> {
+        Box::new(DLABuilder {
+            algorithm,
+            brush_size,
+            symmetry,
+            floor_percent,
+        })
+    }
+
     #[allow(dead_code)]
     pub fn walk_inwards() -> Box<DLABuilder> {
         Box::new(DLABuilder {
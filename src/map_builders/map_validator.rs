@@ -0,0 +1,181 @@
+use super::{tile_walkable, BuilderMap, MetaMapBuilder, Position, TileType};
+use rltk::RandomNumberGenerator;
+
+/// Checks that a finished map is playable. Problems that can be fixed in place (a blocked
+/// start, unreachable stairs, spawns in walls) are repaired; problems that can't are recorded
+/// in `build_data.validation_errors` so the level can be regenerated.
+pub struct MapValidator {
+    min_floor_ratio: f32,
+}
+
+impl MetaMapBuilder for MapValidator {
+    fn build_map(&mut self, rng: &mut rltk::RandomNumberGenerator, build_data: &mut BuilderMap) {
+        self.build(rng, build_data);
+    }
+}
+
+impl MapValidator {
+    #[allow(dead_code)]
+    pub fn new() -> Box<MapValidator> {
+        Box::new(MapValidator {
+            min_floor_ratio: 0.1,
+        })
+    }
+
+    fn build(&mut self, _rng: &mut RandomNumberGenerator, build_data: &mut BuilderMap) {
+        let depth = build_data.map.depth;
+        build_data.map.populate_blocked();
+
+        // The player has to start somewhere they can stand
+        let start_idx = match self.check_start(build_data) {
+            Some(idx) => idx,
+            None => {
+                self.reject(
+                    build_data,
+                    "there is nowhere to place the player".to_string(),
+                );
+                return;
+            }
+        };
+
+        let map_starts: Vec<usize> = vec![start_idx];
+        let dijkstra_map = rltk::DijkstraMap::new(
+            build_data.map.width as usize,
+            build_data.map.height as usize,
+            &map_starts,
+            &build_data.map,
+            1000.0,
+        );
+        let reachable = |idx: usize| dijkstra_map.map[idx] != f32::MAX;
+
+        // Enough of the map has to be reachable to be worth playing
+        let reachable_tiles = (0..build_data.map.tiles.len())
+            .filter(|idx| tile_walkable(build_data.map.tiles[*idx]) && reachable(*idx))
+            .count();
+        let floor_ratio = reachable_tiles as f32 / build_data.map.tiles.len() as f32;
+        if floor_ratio < self.min_floor_ratio {
+            self.reject(
+                build_data,
+                format!(
+                    "only {:.0}% of the map is reachable (need {:.0}%)",
+                    floor_ratio * 100.0,
+                    self.min_floor_ratio * 100.0
+                ),
+            );
+            return;
+        }
+
+        // There must be a reachable way down. If there isn't, move the stairs to the far end of
+        // the reachable area.
+        let has_stairs = build_data.map.tiles.contains(&TileType::DownStairs);
+        let stairs_reachable = build_data
+            .map
+            .tiles
+            .iter()
+            .enumerate()
+            .any(|(idx, tt)| *tt == TileType::DownStairs && reachable(idx));
+        if !stairs_reachable {
+            for tile in build_data.map.tiles.iter_mut() {
+                if *tile == TileType::DownStairs {
+                    *tile = TileType::Floor;
+                }
+            }
+            let mut exit_tile = (start_idx, 0.0f32);
+            for (idx, tile) in build_data.map.tiles.iter().enumerate() {
+                if *tile == TileType::Floor && reachable(idx) && dijkstra_map.map[idx] > exit_tile.1
+                {
+                    exit_tile = (idx, dijkstra_map.map[idx]);
+                }
+            }
+            if exit_tile.0 == start_idx {
+                self.reject(
+                    build_data,
+                    "there is nowhere to put the down stairs".to_string(),
+                );
+                return;
+            }
+            build_data.map.tiles[exit_tile.0] = TileType::DownStairs;
+            self.repaired(
+                depth,
+                if has_stairs {
+                    "the down stairs were unreachable, so they were moved"
+                } else {
+                    "there were no down stairs, so some were added"
+                },
+            );
+        }
+
+        // Nothing should spawn inside a wall, on the stairs or the player, or where the player
+        // can't get to
+        let tiles = &build_data.map.tiles;
+        let before = build_data.spawn_list.len();
+        build_data.spawn_list.retain(|(idx, _name)| {
+            *idx != start_idx
+                && tile_walkable(tiles[*idx])
+                && tiles[*idx] != TileType::DownStairs
+                && reachable(*idx)
+        });
+        let removed = before - build_data.spawn_list.len();
+        if removed > 0 {
+            self.repaired(
+                depth,
+                &format!(
+                    "removed {} spawns that were blocked or unreachable",
+                    removed
+                ),
+            );
+        }
+
+        build_data.take_snapshot();
+    }
+
+    /// Returns the starting tile, moving the start to the nearest walkable tile if needed.
+    fn check_start(&self, build_data: &mut BuilderMap) -> Option<usize> {
+        let start = build_data
+            .starting_position
+            .as_ref()
+            .map(|pos| (pos.x, pos.y))
+            .unwrap_or((build_data.map.width / 2, build_data.map.height / 2));
+        let start_idx = build_data.map.xy_idx(start.0, start.1);
+        if build_data.starting_position.is_some() && tile_walkable(build_data.map.tiles[start_idx])
+        {
+            return Some(start_idx);
+        }
+
+        let width = build_data.map.width;
+        let nearest = build_data
+            .map
+            .tiles
+            .iter()
+            .enumerate()
+            .filter(|(_idx, tt)| **tt == TileType::Floor)
+            .map(|(idx, _tt)| idx)
+            .min_by_key(|idx| {
+                let x = *idx as i32 % width;
+                let y = *idx as i32 / width;
+                (x - start.0).abs() + (y - start.1).abs()
+            })?;
+
+        build_data.starting_position = Some(Position {
+            x: nearest as i32 % width,
+            y: nearest as i32 / width,
+        });
+        self.repaired(
+            build_data.map.depth,
+            "the starting position was blocked, so it was moved",
+        );
+        Some(nearest)
+    }
+
+    fn repaired(&self, depth: i32, reason: &str) {
+        rltk::console::log(format!("Map validation (depth {}): {}", depth, reason));
+    }
+
+    fn reject(&self, build_data: &mut BuilderMap, reason: String) {
+        rltk::console::log(format!(
+            "Map validation (depth {}) failed: {}",
+            build_data.map.depth, reason
+        ));
+        build_data.validation_errors.push(reason);
+    }
+}
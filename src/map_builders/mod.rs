@@ -1,8 +1,10 @@
-use super::{spawner, Map, Position, Rect, TileType, SHOW_MAPGEN_VISUALIZER};
+use super::{spawner, tile_walkable, Map, Position, Rect, TileType, SHOW_MAPGEN_VISUALIZER};
+use crate::raws::{get_level_chain, get_level_chain_table, level_chain_names, RAWS};
 use specs::prelude::*;
 mod area_starting_points;
 mod bsp_dungeon;
 mod bsp_interior;
+mod builder_registry;
 mod cellular_automata;
 mod common;
 mod cull_unreachable;
@@ -10,6 +12,7 @@ mod distant_exit;
 mod dla;
 mod door_placement;
 mod drunkard;
+mod map_validator;
 mod maze;
 mod prefab_builder;
 mod room_based_spawner;
@@ -29,35 +32,15 @@ mod town;
 mod voronoi;
 mod voronoi_spawning;
 mod waveform_collapse;
-use area_starting_points::{AreaStartingPosition, XStart, YStart};
-use bsp_dungeon::BspDungeonBuilder;
-use bsp_interior::BspInteriorBuilder;
-use cellular_automata::CellularAutomataBuilder;
+pub use builder_registry::BuilderRegistry;
 use common::*;
-use cull_unreachable::CullUnreachable;
-use distant_exit::DistantExit;
-use dla::DLABuilder;
-use door_placement::DoorPlacement;
-use drunkard::DrunkardsWalkBuilder;
-use maze::MazeBuilder;
-use prefab_builder::PrefabBuilder;
+use map_validator::MapValidator;
 use room_based_spawner::RoomBasedSpawner;
 use room_based_stairs::RoomBasedStairs;
 use room_based_starting_position::RoomBasedStartingPosition;
-use room_corner_rounding::RoomCornerRounder;
-use room_corridor_spawner::CorridorSpawner;
 use room_draw::RoomDrawer;
-use room_exploder::RoomExploder;
-use room_sorter::{RoomSort, RoomSorter};
-use rooms_corridors_bsp::BspCorridors;
-use rooms_corridors_dogleg::DoglegCorridors;
-use rooms_corridors_lines::StraightLineCorridors;
 use rooms_corridors_nearest::NearestCorridors;
 use simple_map::SimpleMapBuilder;
-use town::town_builder;
-use voronoi::VoronoiCellBuilder;
-use voronoi_spawning::VoronoiSpawning;
-use waveform_collapse::WaveformCollapseBuilder;
 
 pub struct BuilderMap {
     pub spawn_list: Vec<(usize, String)>,
@@ -66,13 +49,15 @@ pub struct BuilderMap {
     pub rooms: Option<Vec<Rect>>,
     pub corridors: Option<Vec<Vec<usize>>>,
     pub history: Vec<Map>,
+    pub record_history: bool,
+    pub validation_errors: Vec<String>,
     pub width: i32,
     pub height: i32,
 }
 
 impl BuilderMap {
     fn take_snapshot(&mut self) {
-        if SHOW_MAPGEN_VISUALIZER {
+        if self.record_history {
             let mut snapshot = self.map.clone();
             for v in snapshot.revealed_tiles.iter_mut() {
                 *v = true;
@@ -85,14 +70,18 @@ impl BuilderMap {
 pub struct BuilderChain {
     starter: Option<Box<dyn InitialMapBuilder>>,
     builders: Vec<Box<dyn MetaMapBuilder>>,
+    pub seed: u64,
     pub build_data: BuilderMap,
 }
 
 impl BuilderChain {
-    pub fn new(new_depth: i32, width: i32, height: i32) -> BuilderChain {
+    /// Creates an empty chain. Every random roll made while building comes from `seed`, so
+    /// the same chain and seed always produce the same map.
+    pub fn new(new_depth: i32, seed: u64, width: i32, height: i32) -> BuilderChain {
         BuilderChain {
             starter: None,
             builders: Vec::new(),
+            seed,
             build_data: BuilderMap {
                 spawn_list: Vec::new(),
                 map: Map::new(new_depth, width, height),
@@ -100,6 +89,8 @@ impl BuilderChain {
                 rooms: None,
                 corridors: None,
                 history: Vec::new(),
+                record_history: SHOW_MAPGEN_VISUALIZER,
+                validation_errors: Vec::new(),
                 width,
                 height,
             },
@@ -117,18 +108,19 @@ impl BuilderChain {
         self.builders.push(metabuilder);
     }
 
-    pub fn build_map(&mut self, rng: &mut rltk::RandomNumberGenerator) {
+    pub fn build_map(&mut self) {
+        let mut rng = rltk::RandomNumberGenerator::seeded(self.seed);
         match &mut self.starter {
             None => panic!("Cannot run a map builder chain without a starting build system"),
             Some(starter) => {
                 // Build the starting map
-                starter.build_map(rng, &mut self.build_data);
+                starter.build_map(&mut rng, &mut self.build_data);
             }
         }
 
         // Build additional layers in turn
         for metabuilder in self.builders.iter_mut() {
-            metabuilder.build_map(rng, &mut self.build_data);
+            metabuilder.build_map(&mut rng, &mut self.build_data);
         }
     }
 
@@ -147,170 +139,128 @@ pub trait MetaMapBuilder {
     fn build_map(&mut self, rng: &mut rltk::RandomNumberGenerator, build_data: &mut BuilderMap);
 }
 
-fn random_start_position(rng: &mut rltk::RandomNumberGenerator) -> (XStart, YStart) {
-    let x;
-    let xroll = rng.roll_dice(1, 3);
-    match xroll {
-        1 => x = XStart::LEFT,
-        2 => x = XStart::CENTER,
-        _ => x = XStart::RIGHT,
-    }
-
-    let y;
-    let yroll = rng.roll_dice(1, 3);
-    match yroll {
-        1 => y = YStart::BOTTOM,
-        2 => y = YStart::CENTER,
-        _ => y = YStart::TOP,
-    }
-
-    (x, y)
+/// A plain rooms-and-corridors chain, used if the raws don't provide one for a depth.
+fn fallback_builder(
+    new_depth: i32,
+    rng: &mut rltk::RandomNumberGenerator,
+    width: i32,
+    height: i32,
+) -> BuilderChain {
+    let mut builder = BuilderChain::new(new_depth, rng.next_u64(), width, height);
+    builder.start_with(SimpleMapBuilder::new());
+    builder.with(RoomDrawer::new());
+    builder.with(NearestCorridors::new());
+    builder.with(RoomBasedStartingPosition::new());
+    builder.with(RoomBasedStairs::new());
+    builder.with(RoomBasedSpawner::new());
+    builder.with(MapValidator::new());
+    builder
 }
 
-fn random_room_builder(rng: &mut rltk::RandomNumberGenerator, builder: &mut BuilderChain) {
-    let build_roll = rng.roll_dice(1, 3);
-    match build_roll {
-        1 => builder.start_with(SimpleMapBuilder::new()),
-        2 => builder.start_with(BspDungeonBuilder::new()),
-        _ => builder.start_with(BspInteriorBuilder::new()),
-    }
-
-    // BSP Interior still makes holes in the walls
-    if build_roll != 3 {
-        // Sort by one of the 5 available algorithms
-        let sort_roll = rng.roll_dice(1, 5);
-        match sort_roll {
-            1 => builder.with(RoomSorter::new(RoomSort::LEFTMOST)),
-            2 => builder.with(RoomSorter::new(RoomSort::RIGHTMOST)),
-            3 => builder.with(RoomSorter::new(RoomSort::TOPMOST)),
-            4 => builder.with(RoomSorter::new(RoomSort::BOTTOMMOST)),
-            _ => builder.with(RoomSorter::new(RoomSort::CENTRAL)),
-        }
-
-        builder.with(RoomDrawer::new());
-
-        let corridor_roll = rng.roll_dice(1, 4);
-        match corridor_roll {
-            1 => builder.with(DoglegCorridors::new()),
-            2 => builder.with(NearestCorridors::new()),
-            3 => builder.with(StraightLineCorridors::new()),
-            _ => builder.with(BspCorridors::new()),
+/// Picks the builder chain for a depth from `raws/levels.json`. `seed` drives both the choice
+/// of chain and the chain itself, so a given depth seed always yields the same level.
+pub fn level_builder(new_depth: i32, seed: u64, width: i32, height: i32) -> BuilderChain {
+    let mut rng = rltk::RandomNumberGenerator::seeded(seed);
+    let chain = {
+        let raws = &RAWS.lock().unwrap();
+        let chain_name = get_level_chain_table(raws, new_depth).roll(&mut rng);
+        get_level_chain(raws, &chain_name)
+    };
+
+    let built = match &chain {
+        Some(chain) => {
+            BuilderRegistry::new().build_chain(chain, new_depth, &mut rng, width, height)
         }
-
-        let cspawn_roll = rng.roll_dice(1, 2);
-        if cspawn_roll == 1 {
-            builder.with(CorridorSpawner::new());
+        None => Err(format!(
+            "no level chain is available for depth {}",
+            new_depth
+        )),
+    };
+    match built {
+        Ok(mut builder) => {
+            builder.with(MapValidator::new());
+            builder
         }
-
-        let modifier_roll = rng.roll_dice(1, 6);
-        match modifier_roll {
-            1 => builder.with(RoomExploder::new()),
-            2 => builder.with(RoomCornerRounder::new()),
-            _ => {}
+        Err(e) => {
+            rltk::console::log(format!("WARNING - {}; using the fallback builder", e));
+            fallback_builder(new_depth, &mut rng, width, height)
         }
     }
-
-    let start_roll = rng.roll_dice(1, 2);
-    match start_roll {
-        1 => builder.with(RoomBasedStartingPosition::new()),
-        _ => {
-            let (start_x, start_y) = random_start_position(rng);
-            builder.with(AreaStartingPosition::new(start_x, start_y));
-        }
-    }
-
-    let exit_roll = rng.roll_dice(1, 2);
-    match exit_roll {
-        1 => builder.with(RoomBasedStairs::new()),
-        _ => builder.with(DistantExit::new()),
-    }
-
-    let spawn_roll = rng.roll_dice(1, 2);
-    match spawn_roll {
-        1 => builder.with(RoomBasedSpawner::new()),
-        _ => builder.with(VoronoiSpawning::new()),
-    }
 }
 
-fn random_shape_builder(rng: &mut rltk::RandomNumberGenerator, builder: &mut BuilderChain) {
-    let builder_roll = rng.roll_dice(1, 16);
-    match builder_roll {
-        1 => builder.start_with(CellularAutomataBuilder::new()),
-        2 => builder.start_with(DrunkardsWalkBuilder::open_area()),
-        3 => builder.start_with(DrunkardsWalkBuilder::open_halls()),
-        4 => builder.start_with(DrunkardsWalkBuilder::winding_passages()),
-        5 => builder.start_with(DrunkardsWalkBuilder::fat_passages()),
-        6 => builder.start_with(DrunkardsWalkBuilder::fearful_symmetry()),
-        7 => builder.start_with(MazeBuilder::new()),
-        8 => builder.start_with(DLABuilder::walk_inwards()),
-        9 => builder.start_with(DLABuilder::walk_outwards()),
-        10 => builder.start_with(DLABuilder::central_attractor()),
-        11 => builder.start_with(DLABuilder::insectoid()),
-        12 => builder.start_with(VoronoiCellBuilder::pythagoras()),
-        13 => builder.start_with(VoronoiCellBuilder::manhattan()),
-        _ => builder.start_with(PrefabBuilder::constant(
-            prefab_builder::prefab_levels::WFC_POPULATED,
-        )),
-    }
-
-    // Set the start to the center and cull
-    builder.with(AreaStartingPosition::new(XStart::CENTER, YStart::CENTER));
-    builder.with(CullUnreachable::new());
+const MAX_LEVEL_ATTEMPTS: i32 = 10;
 
-    // Now set the start to a random starting area
-    let (start_x, start_y) = random_start_position(rng);
-    builder.with(AreaStartingPosition::new(start_x, start_y));
-
-    // Setup an exit and spawn mobs
-    builder.with(VoronoiSpawning::new());
-    builder.with(DistantExit::new());
-}
-
-pub fn random_builder(
+/// Builds the level for a depth, starting over with a new seed (derived from `seed`) whenever
+/// the map validator rejects the result. Snapshots are only kept if `record_history` is set.
+pub fn build_level(
     new_depth: i32,
-    rng: &mut rltk::RandomNumberGenerator,
+    seed: u64,
     width: i32,
     height: i32,
+    record_history: bool,
 ) -> BuilderChain {
-    let mut builder = BuilderChain::new(new_depth, width, height);
-    let type_roll = rng.roll_dice(1, 2);
-    match type_roll {
-        1 => random_room_builder(rng, &mut builder),
-        _ => random_shape_builder(rng, &mut builder),
-    }
-
-    if rng.roll_dice(1, 3) == 1 {
-        builder.with(WaveformCollapseBuilder::new());
-
-        // Now set the start to a random starting area
-        let (start_x, start_y) = random_start_position(rng);
-        builder.with(AreaStartingPosition::new(start_x, start_y));
-
-        // Setup an exit and spawn mobs
-        builder.with(VoronoiSpawning::new());
-        builder.with(DistantExit::new());
-    }
-
-    if rng.roll_dice(1, 20) == 1 {
-        builder.with(PrefabBuilder::sectional(
-            prefab_builder::prefab_sections::UNDERGROUND_FORT,
+    let mut rng = rltk::RandomNumberGenerator::seeded(seed);
+    let mut attempt_seed = seed;
+    let mut attempt = 1;
+    loop {
+        let mut builder = level_builder(new_depth, attempt_seed, width, height);
+        builder.build_data.record_history = record_history;
+        builder.build_map();
+        if builder.build_data.validation_errors.is_empty() || attempt == MAX_LEVEL_ATTEMPTS {
+            return builder;
+        }
+        rltk::console::log(format!(
+            "Regenerating depth {} (attempt {}): {}",
+            new_depth,
+            attempt,
+            builder.build_data.validation_errors.join("; ")
         ));
+        attempt_seed = rng.next_u64();
+        attempt += 1;
     }
+}
 
-    builder.with(DoorPlacement::new());
-    builder.with(PrefabBuilder::vaults());
-
+/// Builds a depth exactly as the game does when the player first arrives: the validated level,
+/// plus a way back up where the player starts on everything below the town.
+pub fn dungeon_level(
+    new_depth: i32,
+    seed: u64,
+    width: i32,
+    height: i32,
+    record_history: bool,
+) -> BuilderChain {
+    let mut builder = build_level(new_depth, seed, width, height, record_history);
+    if new_depth > 1 {
+        if let Some(pos) = &builder.build_data.starting_position {
+            let up_idx = builder.build_data.map.xy_idx(pos.x, pos.y);
+            builder.build_data.map.tiles[up_idx] = TileType::UpStairs;
+        }
+    }
     builder
 }
 
-pub fn level_builder(
+/// Builds a chain from `raws/levels.json` by name, for tools that generate maps outside of
+/// the game.
+pub fn named_builder(
+    name: &str,
     new_depth: i32,
-    rng: &mut rltk::RandomNumberGenerator,
+    seed: u64,
     width: i32,
     height: i32,
-) -> BuilderChain {
-    match new_depth {
-        1 => town_builder(new_depth, rng, width, height),
-        _ => random_builder(new_depth, rng, width, height),
+) -> Result<BuilderChain, String> {
+    let raws = &RAWS.lock().unwrap();
+    match get_level_chain(raws, name) {
+        Some(chain) => {
+            let mut rng = rltk::RandomNumberGenerator::seeded(seed);
+            let mut builder =
+                BuilderRegistry::new().build_chain(&chain, new_depth, &mut rng, width, height)?;
+            builder.with(MapValidator::new());
+            Ok(builder)
+        }
+        None => Err(format!(
+            "Unknown builder chain '{}'. Available chains: level, {}",
+            name,
+            level_chain_names(raws).join(", ")
+        )),
     }
 }
@@ -1,4 +1,4 @@
-use super::{BuilderChain, BuilderMap, InitialMapBuilder, Position, TileType};
+use super::{BuilderMap, InitialMapBuilder, Position, TileType};
 use std::collections::HashSet;
 
 pub struct TownBuilder {}
@@ -39,7 +39,6 @@ impl TownBuilder {
 
         // Sort buildings by size
         let building_size = self.sort_buildings(&buildings);
-        self.building_factory(rng, build_data, &buildings, &building_size);
 
         // Start in the pub, which is the largest building
         let the_pub = &buildings[building_size[0].0];
@@ -48,6 +47,8 @@ impl TownBuilder {
             y: the_pub.1 + (the_pub.3 / 2),
         });
 
+        self.building_factory(rng, build_data, &buildings, &building_size);
+
         // Make visible for screenshot
         for t in build_data.map.visible_tiles.iter_mut() {
             *t = true;
@@ -313,47 +314,31 @@ impl TownBuilder {
         buildings: &[(i32, i32, i32, i32)],
         building_index: &[(usize, i32, BuildingTag)],
     ) {
-        for (i, building) in buildings.iter().enumerate() {
-            let build_type = &building_index[i].2;
-            match build_type {
-                BuildingTag::Pub => self.build_pub(&building, build_data, rng),
-                _ => {}
-            }
+        for (building_id, _size, build_type) in building_index.iter() {
+            let building = &buildings[*building_id];
+            let mut to_place = furnishings(build_type, rng);
+            self.furnish_building(building, build_data, rng, &mut to_place);
         }
     }
 
-    fn build_pub(
+    /// Scatters a building's NPCs and props over its floor, keeping the player's starting tile
+    /// clear.
+    fn furnish_building(
         &mut self,
         building: &(i32, i32, i32, i32),
         build_data: &mut BuilderMap,
         rng: &mut rltk::RandomNumberGenerator,
+        to_place: &mut Vec<&str>,
     ) {
-        // Place the player
-        build_data.starting_position = Some(Position {
-            x: building.0 + (building.2 / 2),
-            y: building.1 + (building.3 / 2),
-        });
         let player_idx = build_data
-            .map
-            .xy_idx(building.0 + (building.2 / 2), building.1 + (building.3 / 2));
-
-        // Place other items
-        let mut to_place: Vec<&str> = vec![
-            "Barkeep",
-            "Shady Salesman",
-            "Patron",
-            "Patron",
-            "Keg",
-            "Table",
-            "Chair",
-            "Table",
-            "Chair",
-        ];
+            .starting_position
+            .as_ref()
+            .map(|pos| build_data.map.xy_idx(pos.x, pos.y));
         for y in building.1..building.1 + building.3 {
             for x in building.0..building.0 + building.2 {
                 let idx = build_data.map.xy_idx(x, y);
                 if build_data.map.tiles[idx] == TileType::WoodFloor
-                    && idx != player_idx
+                    && Some(idx) != player_idx
                     && rng.roll_dice(1, 3) == 1
                     && !to_place.is_empty()
                 {
@@ -366,13 +351,57 @@ impl TownBuilder {
     }
 }
 
-pub fn town_builder(
-    new_depth: i32,
-    _rng: &mut rltk::RandomNumberGenerator,
-    width: i32,
-    height: i32,
-) -> BuilderChain {
-    let mut chain = BuilderChain::new(new_depth, width, height);
-    chain.start_with(TownBuilder::new());
-    chain
+/// What goes in each kind of building. Every name here is a mob or prop in the raws.
+fn furnishings(
+    build_type: &BuildingTag,
+    rng: &mut rltk::RandomNumberGenerator,
+) -> Vec<&'static str> {
+    match build_type {
+        BuildingTag::Pub => vec![
+            "Barkeep",
+            "Shady Salesman",
+            "Patron",
+            "Patron",
+            "Keg",
+            "Table",
+            "Chair",
+            "Table",
+            "Chair",
+        ],
+        BuildingTag::Temple => vec![
+            "Priest",
+            "Altar",
+            "Parishioner",
+            "Parishioner",
+            "Chair",
+            "Chair",
+            "Candle",
+            "Candle",
+        ],
+        BuildingTag::Blacksmith => vec![
+            "Blacksmith",
+            "Anvil",
+            "Water Trough",
+            "Weapon Rack",
+            "Armor Stand",
+        ],
+        BuildingTag::Clothier => vec!["Clothier", "Cabinet", "Table", "Loom", "Hide Rack"],
+        BuildingTag::Alchemist => vec![
+            "Alchemist",
+            "Chemistry Set",
+            "Shelf",
+            "Dead Thing",
+            "Chair",
+            "Table",
+        ],
+        BuildingTag::PlayerHouse => vec!["Mom", "Bed", "Cabinet", "Chair", "Table"],
+        BuildingTag::Hovel => vec!["Peasant", "Bed", "Chair", "Table"],
+        BuildingTag::Abandoned => {
+            let rats = rng.roll_dice(1, 3) + 1;
+            let mut contents = vec!["Rat"; rats as usize];
+            contents.push("Bed");
+            contents
+        }
+        BuildingTag::Unassigned => Vec::new(),
+    }
 }
@@ -30,6 +30,17 @@ impl VoronoiCellBuilder {
         })
     }
 
+    #[allow(dead_code)]
+    pub fn custom(
+        n_seeds: usize,
+        distance_algorithm: DistanceAlgorithm,
+    ) -> Box<VoronoiCellBuilder> {
+        Box::new(VoronoiCellBuilder {
+            n_seeds,
+            distance_algorithm,
+        })
+    }
+
     #[allow(dead_code)]
     pub fn pythagoras() -> Box<VoronoiCellBuilder> {
         Box::new(VoronoiCellBuilder {
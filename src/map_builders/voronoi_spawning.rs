@@ -40,12 +40,14 @@ impl VoronoiSpawning {
             }
         }
 
-        // Spawn the entities
-        for area in noise_areas.iter() {
+        // Spawn the entities, visiting the areas in a fixed order so seeded runs repeat
+        let mut area_keys: Vec<&i32> = noise_areas.keys().collect();
+        area_keys.sort();
+        for area in area_keys.iter() {
             spawner::spawn_region(
                 &build_data.map,
                 rng,
-                area.1,
+                &noise_areas[area],
                 build_data.map.depth,
                 &mut build_data.spawn_list,
             );
@@ -68,8 +68,9 @@ pub fn build_patterns(
             "Pre de-duplication, there are {} patterns",
             patterns.len()
         ));
-        let set: HashSet<Vec<TileType>> = patterns.drain(..).collect(); // dedup
-        patterns.extend(set.into_iter());
+        // Keep the first copy of each pattern, preserving order so seeded runs repeat
+        let mut seen: HashSet<Vec<TileType>> = HashSet::new();
+        patterns.retain(|p| seen.insert(p.clone()));
         rltk::console::log(format!("There are {} patterns", patterns.len()));
     }
 
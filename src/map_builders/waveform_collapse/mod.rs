@@ -29,9 +29,8 @@ impl WaveformCollapseBuilder {
 
         let patterns = build_patterns(&build_data.map, CHUNK_SIZE, true, true);
         let constraints = patterns_to_constraints(patterns, CHUNK_SIZE);
-        self.render_tile_gallery(&constraints, CHUNK_SIZE, build_data);
-
         let old_map = build_data.map.clone();
+        self.render_tile_gallery(&constraints, CHUNK_SIZE, build_data);
 
         build_data.map = Map::new(build_data.map.depth, build_data.width, build_data.height);
         build_data.spawn_list.clear();
@@ -65,7 +64,7 @@ impl WaveformCollapseBuilder {
         chunk_size: i32,
         build_data: &mut BuilderMap,
     ) {
-        build_data.map = Map::new(0, build_data.width, build_data.height);
+        build_data.map = Map::new(build_data.map.depth, build_data.width, build_data.height);
         let mut counter = 0;
         let mut x = 1;
         let mut y = 1;
@@ -81,7 +80,8 @@ impl WaveformCollapseBuilder {
                 if y + chunk_size > build_data.map.height {
                     // Move to the next page
                     build_data.take_snapshot();
-                    build_data.map = Map::new(0, build_data.width, build_data.height);
+                    build_data.map =
+                        Map::new(build_data.map.depth, build_data.width, build_data.height);
 
                     x = 1;
                     y = 1;
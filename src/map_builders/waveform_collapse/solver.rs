@@ -200,6 +200,8 @@ impl Solver {
                 }
             }
 
+            possible_options.sort_unstable();
+
             if possible_options.is_empty() {
                 rltk::console::log("Oh no! It's not possible!");
                 self.possible = false;
@@ -0,0 +1,223 @@
+use super::map_builders::{dungeon_level, named_builder, BuilderChain};
+use super::{Map, MasterDungeonMap, TileType};
+use serde::Serialize;
+use std::fs;
+
+const USAGE: &str = "usage: mapgen [--chain <name>] [--depth <n>] [--width <n>] [--height <n>] \
+                     [--seed <n>] [--history] [--out <path prefix>]";
+
+struct MapgenOptions {
+    chain: String,
+    depth: i32,
+    width: i32,
+    height: i32,
+    seed: Option<u64>,
+    history: bool,
+    out: String,
+}
+
+#[derive(Serialize)]
+struct MapgenPosition {
+    x: i32,
+    y: i32,
+}
+
+#[derive(Serialize)]
+struct MapgenSpawn {
+    idx: usize,
+    x: i32,
+    y: i32,
+    name: String,
+}
+
+#[derive(Serialize)]
+struct MapgenOutput {
+    chain: String,
+    depth: i32,
+    seed: u64,
+    width: i32,
+    height: i32,
+    tiles: Vec<TileType>,
+    starting_position: Option<MapgenPosition>,
+    spawn_list: Vec<MapgenSpawn>,
+    history: Vec<Vec<TileType>>,
+}
+
+/// Runs a builder chain without opening a window, writing the result to `<out>.txt` (ASCII)
+/// and `<out>.json`. Used by `roguelike-tut mapgen ...`.
+pub fn run(args: &[String]) -> Result<(), String> {
+    let options = parse_args(args)?;
+    let seed = options
+        .seed
+        .unwrap_or_else(|| rltk::RandomNumberGenerator::new().next_u64());
+    // `--seed` is the run seed the game shows; each depth is built from its own seed derived
+    // from it, just as in the game
+    let depth_seed = MasterDungeonMap::new(seed).depth_seed(options.depth);
+
+    let builder = if options.chain == "level" {
+        // Exactly what the game would build for this depth, including regeneration
+        dungeon_level(
+            options.depth,
+            depth_seed,
+            options.width,
+            options.height,
+            options.history,
+        )
+    } else {
+        let mut builder = named_builder(
+            &options.chain,
+            options.depth,
+            depth_seed,
+            options.width,
+            options.height,
+        )?;
+        builder.build_data.record_history = options.history;
+        builder.build_map();
+        builder
+    };
+
+    let text_path = format!("{}.txt", options.out);
+    let json_path = format!("{}.json", options.out);
+    fs::write(&text_path, ascii_dump(&options, seed, &builder))
+        .map_err(|e| format!("Unable to write {}: {}", text_path, e))?;
+    let json = serde_json::to_string_pretty(&json_dump(&options, seed, &builder))
+        .map_err(|e| format!("Unable to serialize map: {}", e))?;
+    fs::write(&json_path, json).map_err(|e| format!("Unable to write {}: {}", json_path, e))?;
+
+    println!(
+        "Built '{}' at depth {} with seed {}: wrote {} and {}",
+        options.chain, options.depth, seed, text_path, json_path
+    );
+    Ok(())
+}
+
+fn parse_args(args: &[String]) -> Result<MapgenOptions, String> {
+    let mut options = MapgenOptions {
+        chain: "level".to_string(),
+        depth: 1,
+        width: 80,
+        height: 50,
+        seed: None,
+        history: false,
+        out: "mapgen".to_string(),
+    };
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--history" => options.history = true,
+            "--chain" => options.chain = flag_value(arg, args.next())?.to_string(),
+            "--out" => options.out = flag_value(arg, args.next())?.to_string(),
+            "--depth" => options.depth = parse_number(arg, args.next())?,
+            "--width" => options.width = parse_number(arg, args.next())?,
+            "--height" => options.height = parse_number(arg, args.next())?,
+            "--seed" => options.seed = Some(parse_number(arg, args.next())?),
+            _ => return Err(format!("Unknown argument '{}'\n{}", arg, USAGE)),
+        }
+    }
+
+    if options.width < 10 || options.height < 10 {
+        return Err("Maps must be at least 10x10".to_string());
+    }
+    Ok(options)
+}
+
+fn flag_value<'a>(flag: &str, value: Option<&'a String>) -> Result<&'a str, String> {
+    value
+        .map(|v| v.as_str())
+        .ok_or_else(|| format!("{} requires a value\n{}", flag, USAGE))
+}
+
+fn parse_number<T: std::str::FromStr>(flag: &str, value: Option<&String>) -> Result<T, String> {
+    let value = flag_value(flag, value)?;
+    value
+        .parse::<T>()
+        .map_err(|_| format!("{} expects a number, got '{}'", flag, value))
+}
+
+fn tile_glyph(tile: TileType) -> char {
+    match tile {
+        TileType::Wall => '#',
+        TileType::Floor => '.',
+        TileType::DownStairs => '>',
+        TileType::UpStairs => '<',
+        TileType::Road => '=',
+        TileType::Grass => '"',
+        TileType::Gravel => ':',
+        TileType::ShallowWater => '~',
+        TileType::DeepWater => 'w',
+        TileType::WoodFloor => '_',
+        TileType::Bridge => '%',
+    }
+}
+
+fn ascii_map(map: &Map, start: Option<usize>, spawns: &[(usize, String)]) -> String {
+    let mut glyphs: Vec<char> = map.tiles.iter().map(|t| tile_glyph(*t)).collect();
+    for (idx, _name) in spawns.iter() {
+        glyphs[*idx] = '*';
+    }
+    if let Some(idx) = start {
+        glyphs[idx] = '@';
+    }
+
+    let mut result = String::new();
+    for row in glyphs.chunks(map.width as usize) {
+        result.extend(row.iter());
+        result.push('\n');
+    }
+    result
+}
+
+fn ascii_dump(options: &MapgenOptions, seed: u64, builder: &BuilderChain) -> String {
+    let data = &builder.build_data;
+    let start = data
+        .starting_position
+        .as_ref()
+        .map(|pos| data.map.xy_idx(pos.x, pos.y));
+
+    let mut result = format!(
+        "chain: {}  depth: {}  seed: {}  size: {}x{}\n\n",
+        options.chain, options.depth, seed, data.width, data.height
+    );
+    result.push_str(&ascii_map(&data.map, start, &data.spawn_list));
+
+    result.push_str("\nspawns:\n");
+    for (idx, name) in data.spawn_list.iter() {
+        let x = *idx as i32 % data.width;
+        let y = *idx as i32 / data.width;
+        result.push_str(&format!("  ({}, {}) {}\n", x, y, name));
+    }
+
+    for (i, snapshot) in data.history.iter().enumerate() {
+        result.push_str(&format!("\nhistory {}/{}:\n", i + 1, data.history.len()));
+        result.push_str(&ascii_map(snapshot, None, &[]));
+    }
+    result
+}
+
+fn json_dump(options: &MapgenOptions, seed: u64, builder: &BuilderChain) -> MapgenOutput {
+    let data = &builder.build_data;
+    MapgenOutput {
+        chain: options.chain.clone(),
+        depth: options.depth,
+        seed,
+        width: data.width,
+        height: data.height,
+        tiles: data.map.tiles.clone(),
+        starting_position: data
+            .starting_position
+            .as_ref()
+            .map(|pos| MapgenPosition { x: pos.x, y: pos.y }),
+        spawn_list: data
+            .spawn_list
+            .iter()
+            .map(|(idx, name)| MapgenSpawn {
+                idx: *idx,
+                x: *idx as i32 % data.width,
+                y: *idx as i32 / data.width,
+                name: name.clone(),
+            })
+            .collect(),
+        history: data.history.iter().map(|m| m.tiles.clone()).collect(),
+    }
+}
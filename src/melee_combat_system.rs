@@ -1,6 +1,9 @@
 use super::{
-    gamelog::GameLog, particle_system::ParticleBuilder, CombatStats, DefenseBonus, Equipped,
-    HungerClock, HungerState, MeleePowerBonus, Name, Position, SufferDamage, WantsToMelee,
+    effects::{add_effect, EffectType, Targets},
+    gamelog::GameLog,
+    gamesystem::{skill_bonus, status_hit_modifier},
+    Attributes, Equipped, HungerClock, HungerState, MeleeWeapon, Name, NaturalAttackDefense, Pools,
+    Skill, Skills, StatusEffects, WantsToMelee, WeaponAttribute, Wearable,
 };
 use specs::prelude::*;
 
@@ -13,14 +16,16 @@ impl<'a> System<'a> for MeleeCombatSystem {
         WriteExpect<'a, GameLog>,
         WriteStorage<'a, WantsToMelee>,
         ReadStorage<'a, Name>,
-        ReadStorage<'a, CombatStats>,
-        WriteStorage<'a, SufferDamage>,
-        ReadStorage<'a, MeleePowerBonus>,
-        ReadStorage<'a, DefenseBonus>,
+        ReadStorage<'a, Attributes>,
+        ReadStorage<'a, Skills>,
+        ReadStorage<'a, Pools>,
+        ReadStorage<'a, MeleeWeapon>,
+        ReadStorage<'a, NaturalAttackDefense>,
+        ReadStorage<'a, Wearable>,
         ReadStorage<'a, Equipped>,
-        WriteExpect<'a, ParticleBuilder>,
-        ReadStorage<'a, Position>,
         ReadStorage<'a, HungerClock>,
+        ReadStorage<'a, StatusEffects>,
+        WriteExpect<'a, rltk::RandomNumberGenerator>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
@@ -29,77 +34,201 @@ impl<'a> System<'a> for MeleeCombatSystem {
             mut log,
             mut wants_melee,
             names,
-            combat_stats,
-            mut inflict_damage,
-            melee_power_bonuses,
-            defense_bonuses,
+            attributes,
+            skills,
+            pools,
+            melee_weapons,
+            natural_attacks,
+            wearables,
             equipped,
-            mut particle_builder,
-            positions,
             hunger_clock,
+            status_effects,
+            mut rng,
         ) = data;
 
-        for (entity, wants_melee, name, stats) in
-            (&entities, &wants_melee, &names, &combat_stats).join()
+        for (entity, wants_melee, name, attacker_attributes, attacker_skills, attacker_pools) in (
+            &entities,
+            &wants_melee,
+            &names,
+            &attributes,
+            &skills,
+            &pools,
+        )
+            .join()
         {
-            if stats.hp > 0 {
-                let mut offensive_bonus = 0;
-                for (_item_entity, power_bonus, equipped_by) in
-                    (&entities, &melee_power_bonuses, &equipped).join()
+            // Are the attacker and defender alive? Only attack if they are
+            let target_pools = pools.get(wants_melee.target).unwrap();
+            let target_attributes = attributes.get(wants_melee.target).unwrap();
+            let target_skills = skills.get(wants_melee.target).unwrap();
+            if attacker_pools.hit_points.current > 0 && target_pools.hit_points.current > 0 {
+                let target_name = names.get(wants_melee.target).unwrap();
+
+                // Fight with the equipped weapon. Failing that, creatures use every one of their
+                // natural attacks each turn, and anything without one fights bare-handed
+                let mut attacks: Vec<(MeleeWeapon, Option<String>)> = Vec::new();
+                for (weapon_entity, weapon, equipped_by) in
+                    (&entities, &melee_weapons, &equipped).join()
                 {
                     if equipped_by.owner == entity {
-                        offensive_bonus += power_bonus.power;
+                        attacks = vec![(
+                            weapon.clone(),
+                            names.get(weapon_entity).map(|n| n.name.clone()),
+                        )];
+                    }
+                }
+                if attacks.is_empty() {
+                    if let Some(nat) = natural_attacks.get(entity) {
+                        for attack in nat.attacks.iter() {
+                            let weapon = MeleeWeapon {
+                                attribute: WeaponAttribute::Might,
+                                damage_n_dice: attack.damage_n_dice,
+                                damage_die_type: attack.damage_die_type,
+                                damage_bonus: attack.damage_bonus,
+                                hit_bonus: attack.hit_bonus,
+                            };
+                            attacks.push((weapon, Some(format!("its {}", attack.name))));
+                        }
                     }
                 }
+                if attacks.is_empty() {
+                    let fists = MeleeWeapon {
+                        attribute: WeaponAttribute::Might,
+                        damage_n_dice: 1,
+                        damage_die_type: 4,
+                        damage_bonus: 0,
+                        hit_bonus: 0,
+                    };
+                    attacks.push((fists, None));
+                }
 
-                let hc = hunger_clock.get(entity);
-                if let Some(hc) = hc {
-                    if hc.state == HungerState::WellFed {
-                        offensive_bonus += 1;
+                let base_armor_class = match natural_attacks.get(wants_melee.target) {
+                    Some(nat) => nat.armor_class.unwrap_or(10),
+                    None => 10,
+                };
+                let armor_quickness_bonus = target_attributes.quickness.bonus;
+                let armor_skill_bonus = skill_bonus(Skill::Defense, target_skills);
+                let mut armor_item_bonus_f = 0.0;
+                for (wearable, equipped_by) in (&wearables, &equipped).join() {
+                    if equipped_by.owner == wants_melee.target {
+                        armor_item_bonus_f += wearable.armor_class;
                     }
                 }
+                let armor_item_bonus = armor_item_bonus_f as i32;
+                let armor_class =
+                    base_armor_class + armor_quickness_bonus + armor_skill_bonus + armor_item_bonus;
 
-                let target_stats = combat_stats.get(wants_melee.target).unwrap();
-                if target_stats.hp > 0 {
-                    let target_name = names.get(wants_melee.target).unwrap();
+                for (weapon_info, attack_name) in attacks.iter() {
+                    let with_attack = match attack_name {
+                        Some(attack) => format!(" with {}", attack),
+                        None => String::new(),
+                    };
+                    let attribute_bonus = match weapon_info.attribute {
+                        WeaponAttribute::Might => attacker_attributes.might.bonus,
+                        WeaponAttribute::Quickness => attacker_attributes.quickness.bonus,
+                    };
 
-                    let mut defensive_bonus = 0;
-                    for (_item_entity, defense_bonus, equipped_by) in
-                        (&entities, &defense_bonuses, &equipped).join()
-                    {
-                        if equipped_by.owner == wants_melee.target {
-                            defensive_bonus += defense_bonus.defense;
+                    // Roll to hit: d20 + weapon attribute + melee skill + weapon bonus, against the
+                    // target's armor class
+                    let natural_roll = rng.roll_dice(1, 20);
+                    let attribute_hit_bonus = attribute_bonus;
+                    let weapon_hit_bonus = weapon_info.hit_bonus;
+                    let skill_hit_bonus = skill_bonus(Skill::Melee, attacker_skills);
+                    let mut status_hit_bonus = 0;
+                    if let Some(hc) = hunger_clock.get(entity) {
+                        // Well fed grants +1
+                        if hc.state == HungerState::WellFed {
+                            status_hit_bonus += 1;
                         }
                     }
+                    status_hit_bonus += status_hit_modifier(status_effects.get(entity));
+                    let modified_hit_roll = natural_roll
+                        + attribute_hit_bonus
+                        + skill_hit_bonus
+                        + weapon_hit_bonus
+                        + status_hit_bonus;
 
-                    let pos = positions.get(wants_melee.target);
-                    if let Some(pos) = pos {
-                        particle_builder.request(
-                            pos.x,
-                            pos.y,
-                            rltk::RGB::named(rltk::ORANGE),
-                            rltk::RGB::named(rltk::BLACK),
-                            rltk::to_cp437('‼'),
-                            200.0,
+                    if natural_roll != 1 && (natural_roll == 20 || modified_hit_roll > armor_class)
+                    {
+                        // Target hit! Roll the weapon's damage dice
+                        let base_damage =
+                            rng.roll_dice(weapon_info.damage_n_dice, weapon_info.damage_die_type);
+                        let attr_damage_bonus = attribute_bonus;
+                        let skill_damage_bonus = skill_bonus(Skill::Melee, attacker_skills);
+                        let weapon_damage_bonus = weapon_info.damage_bonus;
+                        let damage = i32::max(
+                            0,
+                            base_damage
+                                + attr_damage_bonus
+                                + skill_damage_bonus
+                                + weapon_damage_bonus,
                         );
-                    }
-
-                    let damage = i32::max(
-                        0,
-                        (stats.power + offensive_bonus) - (target_stats.defense + defensive_bonus),
-                    );
 
-                    if damage == 0 {
+                        if damage == 0 {
+                            log.entries.push(format!(
+                                "{} is unable to hurt {}{}",
+                                &name.name, &target_name.name, with_attack
+                            ));
+                        } else {
+                            log.entries.push(format!(
+                                "{} hits {}{}, for {} hp.",
+                                &name.name, &target_name.name, with_attack, damage
+                            ));
+                            add_effect(
+                                Some(entity),
+                                EffectType::Damage { amount: damage },
+                                Targets::Single {
+                                    target: wants_melee.target,
+                                },
+                            );
+                        }
+                        add_effect(
+                            None,
+                            EffectType::Particle {
+                                glyph: rltk::to_cp437('‼'),
+                                fg: rltk::RGB::named(rltk::ORANGE),
+                                bg: rltk::RGB::named(rltk::BLACK),
+                                lifespan: 200.0,
+                            },
+                            Targets::Single {
+                                target: wants_melee.target,
+                            },
+                        );
+                    } else if natural_roll == 1 {
+                        // Natural 1 miss
                         log.entries.push(format!(
-                            "{} is unable to hurt {}",
-                            &name.name, &target_name.name
+                            "{} considers attacking {}, but misjudges the timing.",
+                            name.name, target_name.name
                         ));
+                        add_effect(
+                            None,
+                            EffectType::Particle {
+                                glyph: rltk::to_cp437('‼'),
+                                fg: rltk::RGB::named(rltk::BLUE),
+                                bg: rltk::RGB::named(rltk::BLACK),
+                                lifespan: 200.0,
+                            },
+                            Targets::Single {
+                                target: wants_melee.target,
+                            },
+                        );
                     } else {
+                        // Miss
                         log.entries.push(format!(
-                            "{} hits {}, for {} hp.",
-                            &name.name, &target_name.name, damage
+                            "{} attacks {}{}, but can't connect.",
+                            name.name, target_name.name, with_attack
                         ));
-                        SufferDamage::new_damage(&mut inflict_damage, wants_melee.target, damage);
+                        add_effect(
+                            None,
+                            EffectType::Particle {
+                                glyph: rltk::to_cp437('‼'),
+                                fg: rltk::RGB::named(rltk::CYAN),
+                                bg: rltk::RGB::named(rltk::BLACK),
+                                lifespan: 200.0,
+                            },
+                            Targets::Single {
+                                target: wants_melee.target,
+                            },
+                        );
                     }
                 }
             }
@@ -1,18 +1,48 @@
 use super::{
-    gamelog::GameLog, BlocksTile, BlocksVisibility, CombatStats, Door, EntityMoved, HungerClock,
-    HungerState, Item, Map, Monster, Player, Position, Renderable, RunState, State, TileType,
-    Viewshed, WantsToMelee, WantsToPickupItem,
+    gamelog::GameLog,
+    raws::{dialogue_start, faction_reaction, Reaction, RAWS},
+    BlocksTile, BlocksVisibility, Dialogue, Door, EntityMoved, Equipped, Faction, HungerClock,
+    HungerState, Item, Map, Name, Player, Pools, Position, Quips, RangedWeapon, Renderable,
+    RunState, State, StatusEffects, StatusKind, TileType, Vendor, VendorMode, Viewshed,
+    WantsToMelee, WantsToPickupItem, WantsToShoot,
 };
 use rltk::{Point, Rltk, VirtualKeyCode};
 use specs::prelude::*;
 use std::cmp::{max, min};
 
-pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) {
+fn player_has_status(ecs: &World, kind: StatusKind) -> bool {
+    let player_entity = ecs.fetch::<Entity>();
+    ecs.read_storage::<StatusEffects>()
+        .get(*player_entity)
+        .is_some_and(|s| s.has(kind))
+}
+
+/// Half the time, a confused player staggers off in a random direction.
+fn confused_direction(ecs: &mut World, delta_x: i32, delta_y: i32) -> (i32, i32) {
+    if !player_has_status(ecs, StatusKind::Confused) {
+        return (delta_x, delta_y);
+    }
+    let mut rng = ecs.write_resource::<rltk::RandomNumberGenerator>();
+    if rng.roll_dice(1, 2) == 1 {
+        return (delta_x, delta_y);
+    }
+    let stagger = (rng.roll_dice(1, 3) - 2, rng.roll_dice(1, 3) - 2);
+    if stagger == (0, 0) {
+        return (delta_x, delta_y);
+    }
+    ecs.fetch_mut::<GameLog>()
+        .entries
+        .push("You stumble around in confusion.".to_string());
+    stagger
+}
+
+pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) -> RunState {
+    let (delta_x, delta_y) = confused_direction(ecs, delta_x, delta_y);
     let mut positions = ecs.write_storage::<Position>();
     let players = ecs.read_storage::<Player>();
     let mut viewsheds = ecs.write_storage::<Viewshed>();
     let entities = ecs.entities();
-    let combat_stats = ecs.read_storage::<CombatStats>();
+    let combat_stats = ecs.read_storage::<Pools>();
     let map = ecs.fetch::<Map>();
     let mut wants_to_melee = ecs.write_storage::<WantsToMelee>();
     let mut entity_moved = ecs.write_storage::<EntityMoved>();
@@ -20,6 +50,11 @@ pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) {
     let mut blocks_visibility = ecs.write_storage::<BlocksVisibility>();
     let mut blocks_movement = ecs.write_storage::<BlocksTile>();
     let mut renderables = ecs.write_storage::<Renderable>();
+    let factions = ecs.read_storage::<Faction>();
+    let vendors = ecs.read_storage::<Vendor>();
+    let dialogues = ecs.read_storage::<Dialogue>();
+    let quips = ecs.read_storage::<Quips>();
+    let mut swap_entities: Vec<(Entity, i32, i32)> = Vec::new();
 
     for (entity, _player, pos, viewshed) in
         (&entities, &players, &mut positions, &mut viewsheds).join()
@@ -29,13 +64,55 @@ pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) {
             || pos.y + delta_y < 1
             || pos.y + delta_y > map.height - 1
         {
-            return;
+            return RunState::Ticking;
         }
         let destination_idx = map.xy_idx(pos.x + delta_x, pos.y + delta_y);
 
         for potential_target in map.tile_content[destination_idx].iter() {
             let target = combat_stats.get(*potential_target);
             if let Some(_target) = target {
+                if !player_is_hostile_to(&factions, *potential_target) {
+                    // Talk to anyone with something to say, or trade with shopkeepers
+                    if let Some(dialogue) = dialogues.get(*potential_target) {
+                        if let Some(node) = dialogue_start(&RAWS.lock().unwrap(), &dialogue.tree) {
+                            return RunState::ShowDialogue {
+                                speaker: *potential_target,
+                                node,
+                            };
+                        }
+                    }
+                    if vendors.get(*potential_target).is_some() {
+                        return RunState::ShowVendor {
+                            vendor: *potential_target,
+                            mode: VendorMode::Sell,
+                        };
+                    }
+                    if let Some(quip) = quips.get(*potential_target) {
+                        if !quip.available.is_empty() {
+                            let mut rng = ecs.write_resource::<rltk::RandomNumberGenerator>();
+                            let roll = rng.roll_dice(1, quip.available.len() as i32);
+                            let line = &quip.available[roll as usize - 1];
+                            if let Some(name) = ecs.read_storage::<Name>().get(*potential_target) {
+                                ecs.fetch_mut::<GameLog>()
+                                    .entries
+                                    .push(format!("{} says \"{}\"", name.name, line));
+                            }
+                        }
+                    }
+
+                    // Friendly folk swap places with us rather than getting stabbed
+                    swap_entities.push((*potential_target, pos.x, pos.y));
+                    pos.x = min(map.width - 1, max(0, pos.x + delta_x));
+                    pos.y = min(map.height - 1, max(0, pos.y + delta_y));
+                    entity_moved
+                        .insert(entity, EntityMoved {})
+                        .expect("Unable to insert marker");
+                    viewshed.dirty = true;
+                    let mut ppos = ecs.write_resource::<Point>();
+                    ppos.x = pos.x;
+                    ppos.y = pos.y;
+                    break;
+                }
                 wants_to_melee
                     .insert(
                         entity,
@@ -44,7 +121,7 @@ pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) {
                         },
                     )
                     .expect("Add target failed");
-                return;
+                return RunState::Ticking;
             }
             let door = doors.get_mut(*potential_target);
             if let Some(door) = door {
@@ -70,6 +147,28 @@ pub fn try_move_player(delta_x: i32, delta_y: i32, ecs: &mut World) {
             ppos.y = pos.y;
         }
     }
+
+    for (swapped, x, y) in swap_entities.iter() {
+        if let Some(their_pos) = positions.get_mut(*swapped) {
+            their_pos.x = *x;
+            their_pos.y = *y;
+        }
+        if let Some(vs) = viewsheds.get_mut(*swapped) {
+            vs.dirty = true;
+        }
+    }
+
+    RunState::Ticking
+}
+
+/// Whether the player's faction wants to fight `target`. Anything without a faction is fair game.
+fn player_is_hostile_to(factions: &ReadStorage<Faction>, target: Entity) -> bool {
+    match factions.get(target) {
+        Some(faction) => {
+            faction_reaction("Player", &faction.name, &RAWS.lock().unwrap()) == Reaction::Attack
+        }
+        None => true,
+    }
 }
 
 pub fn try_next_level(ecs: &mut World) -> bool {
@@ -87,6 +186,21 @@ pub fn try_next_level(ecs: &mut World) -> bool {
     }
 }
 
+pub fn try_previous_level(ecs: &mut World) -> bool {
+    let player_pos = ecs.fetch::<Point>();
+    let map = ecs.fetch::<Map>();
+    let player_idx = map.xy_idx(player_pos.x, player_pos.y);
+    if map.tiles[player_idx] == TileType::UpStairs {
+        true
+    } else {
+        let mut gamelog = ecs.fetch_mut::<GameLog>();
+        gamelog
+            .entries
+            .push("There is no way up from here.".to_string());
+        false
+    }
+}
+
 fn get_item(ecs: &mut World) {
     let player_pos = ecs.fetch::<Point>();
     let player_entity = ecs.fetch::<Entity>();
@@ -124,7 +238,7 @@ fn get_item(ecs: &mut World) {
 fn skip_turn(ecs: &mut World) -> RunState {
     let player_entity = ecs.fetch::<Entity>();
     let viewshed_components = ecs.read_storage::<Viewshed>();
-    let monsters = ecs.read_storage::<Monster>();
+    let factions = ecs.read_storage::<Faction>();
 
     let worldmap_resource = ecs.fetch::<Map>();
 
@@ -133,10 +247,9 @@ fn skip_turn(ecs: &mut World) -> RunState {
     for tile in viewshed.visible_tiles.iter() {
         let idx = worldmap_resource.xy_idx(tile.x, tile.y);
         for entity_id in worldmap_resource.tile_content[idx].iter() {
-            let mob = monsters.get(*entity_id);
-            match mob {
-                None => {}
-                Some(_) => {
+            if let Some(faction) = factions.get(*entity_id) {
+                let reaction = faction_reaction(&faction.name, "Player", &RAWS.lock().unwrap());
+                if reaction == Reaction::Attack {
                     can_heal = false;
                 }
             }
@@ -154,43 +267,153 @@ fn skip_turn(ecs: &mut World) -> RunState {
     }
 
     if can_heal {
-        let mut health_components = ecs.write_storage::<CombatStats>();
-        let player_hp = health_components.get_mut(*player_entity).unwrap();
-        player_hp.hp = i32::min(player_hp.hp + 1, player_hp.max_hp);
+        let mut health_components = ecs.write_storage::<Pools>();
+        let pools = health_components.get_mut(*player_entity).unwrap();
+        pools.hit_points.current = i32::min(pools.hit_points.current + 1, pools.hit_points.max);
     }
 
-    RunState::PlayerTurn
+    RunState::Ticking
+}
+
+/// Fires the player's equipped ranged weapon at the closest visible enemy in range.
+fn fire_at_nearest(ecs: &mut World) -> RunState {
+    let player_entity = ecs.fetch::<Entity>();
+    let entities = ecs.entities();
+    let equipped = ecs.read_storage::<Equipped>();
+    let ranged_weapons = ecs.read_storage::<RangedWeapon>();
+    let factions = ecs.read_storage::<Faction>();
+    let pools = ecs.read_storage::<Pools>();
+    let positions = ecs.read_storage::<Position>();
+    let viewsheds = ecs.read_storage::<Viewshed>();
+    let mut log = ecs.fetch_mut::<GameLog>();
+
+    let mut range = None;
+    for (weapon, equipped_by) in (&ranged_weapons, &equipped).join() {
+        if equipped_by.owner == *player_entity {
+            range = Some(weapon.range);
+        }
+    }
+    let range = match range {
+        Some(range) => range,
+        None => {
+            log.entries
+                .push("You don't have a ranged weapon equipped.".to_string());
+            return RunState::AwaitingInput;
+        }
+    };
+
+    let player_pos = positions.get(*player_entity).unwrap();
+    let player_point = Point::new(player_pos.x, player_pos.y);
+    let viewshed = viewsheds.get(*player_entity).unwrap();
+    let mut nearest: Option<(Entity, f32)> = None;
+    for (entity, _pools, pos) in (&entities, &pools, &positions).join() {
+        if entity == *player_entity || !player_is_hostile_to(&factions, entity) {
+            continue;
+        }
+        let target_point = Point::new(pos.x, pos.y);
+        if !viewshed.visible_tiles.contains(&target_point) {
+            continue;
+        }
+        let distance = rltk::DistanceAlg::Pythagoras.distance2d(player_point, target_point);
+        if distance <= range as f32 && nearest.is_none_or(|(_, d)| distance < d) {
+            nearest = Some((entity, distance));
+        }
+    }
+
+    match nearest {
+        Some((target, _distance)) => {
+            ecs.write_storage::<WantsToShoot>()
+                .insert(*player_entity, WantsToShoot { target })
+                .expect("Unable to insert intent");
+            RunState::Ticking
+        }
+        None => {
+            log.entries
+                .push("There is nothing in range to shoot at.".to_string());
+            RunState::AwaitingInput
+        }
+    }
+}
+
+/// Keys that make the player do something in the world, as opposed to opening a menu.
+fn is_action_key(key: VirtualKeyCode) -> bool {
+    matches!(
+        key,
+        VirtualKeyCode::Left
+            | VirtualKeyCode::Numpad4
+            | VirtualKeyCode::H
+            | VirtualKeyCode::Right
+            | VirtualKeyCode::Numpad6
+            | VirtualKeyCode::L
+            | VirtualKeyCode::Up
+            | VirtualKeyCode::Numpad8
+            | VirtualKeyCode::K
+            | VirtualKeyCode::Down
+            | VirtualKeyCode::Numpad2
+            | VirtualKeyCode::J
+            | VirtualKeyCode::Numpad9
+            | VirtualKeyCode::U
+            | VirtualKeyCode::Numpad7
+            | VirtualKeyCode::Y
+            | VirtualKeyCode::Numpad3
+            | VirtualKeyCode::N
+            | VirtualKeyCode::Numpad1
+            | VirtualKeyCode::B
+            | VirtualKeyCode::Numpad5
+            | VirtualKeyCode::Space
+            | VirtualKeyCode::Period
+            | VirtualKeyCode::Comma
+            | VirtualKeyCode::G
+            | VirtualKeyCode::F
+    )
 }
 
 pub fn player_input(gs: &mut State, ctx: &mut Rltk) -> RunState {
+    // Stunned players lose their turn if they try to act, but can still use menus
+    if ctx.key.is_some_and(is_action_key) && player_has_status(&gs.ecs, StatusKind::Stunned) {
+        gs.ecs
+            .fetch_mut::<GameLog>()
+            .entries
+            .push("You are stunned and can't act!".to_string());
+        return RunState::Ticking;
+    }
+
     // Player movement
     match ctx.key {
         None => return RunState::AwaitingInput, // Nothing happened
         Some(key) => match key {
             VirtualKeyCode::Left | VirtualKeyCode::Numpad4 | VirtualKeyCode::H => {
-                try_move_player(-1, 0, &mut gs.ecs)
+                return try_move_player(-1, 0, &mut gs.ecs)
             }
 
             VirtualKeyCode::Right | VirtualKeyCode::Numpad6 | VirtualKeyCode::L => {
-                try_move_player(1, 0, &mut gs.ecs)
+                return try_move_player(1, 0, &mut gs.ecs)
             }
 
             VirtualKeyCode::Up | VirtualKeyCode::Numpad8 | VirtualKeyCode::K => {
-                try_move_player(0, -1, &mut gs.ecs)
+                return try_move_player(0, -1, &mut gs.ecs)
             }
 
             VirtualKeyCode::Down | VirtualKeyCode::Numpad2 | VirtualKeyCode::J => {
-                try_move_player(0, 1, &mut gs.ecs)
+                return try_move_player(0, 1, &mut gs.ecs)
             }
 
             // Diagonals
-            VirtualKeyCode::Numpad9 | VirtualKeyCode::U => try_move_player(1, -1, &mut gs.ecs),
+            VirtualKeyCode::Numpad9 | VirtualKeyCode::U => {
+                return try_move_player(1, -1, &mut gs.ecs)
+            }
 
-            VirtualKeyCode::Numpad7 | VirtualKeyCode::Y => try_move_player(-1, -1, &mut gs.ecs),
+            VirtualKeyCode::Numpad7 | VirtualKeyCode::Y => {
+                return try_move_player(-1, -1, &mut gs.ecs)
+            }
 
-            VirtualKeyCode::Numpad3 | VirtualKeyCode::N => try_move_player(1, 1, &mut gs.ecs),
+            VirtualKeyCode::Numpad3 | VirtualKeyCode::N => {
+                return try_move_player(1, 1, &mut gs.ecs)
+            }
 
-            VirtualKeyCode::Numpad1 | VirtualKeyCode::B => try_move_player(-1, 1, &mut gs.ecs),
+            VirtualKeyCode::Numpad1 | VirtualKeyCode::B => {
+                return try_move_player(-1, 1, &mut gs.ecs)
+            }
 
             // Skip Turn
             VirtualKeyCode::Numpad5 | VirtualKeyCode::Space => return skip_turn(&mut gs.ecs),
@@ -201,6 +424,11 @@ pub fn player_input(gs: &mut State, ctx: &mut Rltk) -> RunState {
                     return RunState::NextLevel;
                 }
             }
+            VirtualKeyCode::Comma => {
+                if try_previous_level(&mut gs.ecs) {
+                    return RunState::PreviousLevel;
+                }
+            }
 
             // Picking up items
             VirtualKeyCode::G => get_item(&mut gs.ecs),
@@ -208,11 +436,14 @@ pub fn player_input(gs: &mut State, ctx: &mut Rltk) -> RunState {
             VirtualKeyCode::D => return RunState::ShowDropItem,
             VirtualKeyCode::R => return RunState::ShowRemoveItem,
 
+            // Ranged combat
+            VirtualKeyCode::F => return fire_at_nearest(&mut gs.ecs),
+
             // Save and Quit
             VirtualKeyCode::Escape => return RunState::SaveGame,
 
             _ => return RunState::AwaitingInput,
         },
     }
-    RunState::PlayerTurn
+    RunState::Ticking
 }
@@ -0,0 +1,322 @@
+use super::{
+    effects::{add_effect, EffectType, Targets},
+    gamelog::GameLog,
+    gamesystem::{skill_bonus, status_hit_modifier},
+    map::{tile_opaque, tile_walkable},
+    Ammunition, Attributes, Equipped, HungerClock, HungerState, InBackpack, Map, Name,
+    NaturalAttackDefense, Pools, Position, RangedWeapon, Skill, Skills, StatusEffects,
+    WantsToShoot, WeaponAttribute, Wearable,
+};
+use rltk::{LineAlg, Point};
+use specs::prelude::*;
+
+/// Returns true if nothing stands between `start` and `end`: every tile along the line (other
+/// than the two ends) has to be open floor that isn't opaque or occupied.
+pub fn has_line_of_fire(map: &Map, start: Point, end: Point) -> bool {
+    let path = rltk::line2d(LineAlg::Bresenham, start, end);
+    for point in path.iter() {
+        if *point == start || *point == end {
+            continue;
+        }
+        if point.x < 0 || point.x >= map.width || point.y < 0 || point.y >= map.height {
+            return false;
+        }
+        let idx = map.xy_idx(point.x, point.y);
+        if map.blocked[idx] || !tile_walkable(map.tiles[idx]) || tile_opaque(map.tiles[idx]) {
+            return false;
+        }
+    }
+    true
+}
+
+/// Picks a glyph that points along the direction of travel.
+fn projectile_glyph(start: Point, end: Point) -> rltk::FontCharType {
+    let dx = end.x - start.x;
+    let dy = end.y - start.y;
+    if dx.abs() > dy.abs() * 2 {
+        rltk::to_cp437('-')
+    } else if dy.abs() > dx.abs() * 2 {
+        rltk::to_cp437('|')
+    } else if (dx > 0) == (dy > 0) {
+        rltk::to_cp437('\\')
+    } else {
+        rltk::to_cp437('/')
+    }
+}
+
+pub struct RangedCombatSystem {}
+
+impl<'a> System<'a> for RangedCombatSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        Entities<'a>,
+        WriteExpect<'a, GameLog>,
+        ReadExpect<'a, Map>,
+        WriteStorage<'a, WantsToShoot>,
+        ReadStorage<'a, Name>,
+        ReadStorage<'a, Attributes>,
+        ReadStorage<'a, Skills>,
+        ReadStorage<'a, Pools>,
+        ReadStorage<'a, RangedWeapon>,
+        WriteStorage<'a, Ammunition>,
+        ReadStorage<'a, InBackpack>,
+        ReadStorage<'a, NaturalAttackDefense>,
+        ReadStorage<'a, Wearable>,
+        WriteStorage<'a, Equipped>,
+        WriteStorage<'a, Position>,
+        ReadStorage<'a, HungerClock>,
+        ReadStorage<'a, StatusEffects>,
+        WriteExpect<'a, rltk::RandomNumberGenerator>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (
+            entities,
+            mut log,
+            map,
+            mut wants_shoot,
+            names,
+            attributes,
+            skills,
+            pools,
+            ranged_weapons,
+            mut ammunition,
+            backpack,
+            natural_attacks,
+            wearables,
+            mut equipped,
+            mut positions,
+            hunger_clock,
+            status_effects,
+            mut rng,
+        ) = data;
+
+        let mut thrown_weapons: Vec<(Entity, Position)> = Vec::new();
+        let mut spent_ammo: Vec<Entity> = Vec::new();
+
+        for (entity, wants_shoot, name, attacker_attributes, attacker_skills, attacker_pools) in (
+            &entities,
+            &wants_shoot,
+            &names,
+            &attributes,
+            &skills,
+            &pools,
+        )
+            .join()
+        {
+            // Are the attacker and target alive? Only shoot if they are
+            let target = wants_shoot.target;
+            let target_pools = pools.get(target);
+            let target_attributes = attributes.get(target);
+            let target_skills = skills.get(target);
+            let target_name = names.get(target);
+            if target_pools.is_none()
+                || target_attributes.is_none()
+                || target_skills.is_none()
+                || target_name.is_none()
+            {
+                continue;
+            }
+            let target_pools = target_pools.unwrap();
+            let target_attributes = target_attributes.unwrap();
+            let target_skills = target_skills.unwrap();
+            let target_name = target_name.unwrap();
+            if attacker_pools.hit_points.current < 1 || target_pools.hit_points.current < 1 {
+                continue;
+            }
+
+            // What are we shooting with?
+            let mut weapon: Option<(Entity, RangedWeapon)> = None;
+            for (weapon_entity, ranged, equipped_by) in
+                (&entities, &ranged_weapons, &equipped).join()
+            {
+                if equipped_by.owner == entity {
+                    weapon = Some((weapon_entity, ranged.clone()));
+                }
+            }
+            let (weapon_entity, weapon_info) = match weapon {
+                Some(weapon) => weapon,
+                None => {
+                    log.entries
+                        .push(format!("{} has nothing to shoot with.", name.name));
+                    continue;
+                }
+            };
+            let weapon_name = names
+                .get(weapon_entity)
+                .map(|n| n.name.clone())
+                .unwrap_or_else(|| "a missile".to_string());
+
+            let (start, end) = match (positions.get(entity), positions.get(target)) {
+                (Some(from), Some(to)) => (Point::new(from.x, from.y), Point::new(to.x, to.y)),
+                _ => continue,
+            };
+            let distance = rltk::DistanceAlg::Pythagoras.distance2d(start, end);
+            if distance > weapon_info.range as f32 {
+                log.entries.push(format!(
+                    "{} is out of range of {}'s {}.",
+                    target_name.name, name.name, weapon_name
+                ));
+                continue;
+            }
+            if !has_line_of_fire(&map, start, end) {
+                log.entries.push(format!(
+                    "{} has no clear shot at {}.",
+                    name.name, target_name.name
+                ));
+                continue;
+            }
+
+            // Bows and crossbows need ammunition from the backpack
+            if let Some(kind) = &weapon_info.ammo {
+                let mut found_ammo = false;
+                for (ammo_entity, ammo, in_pack) in (&entities, &mut ammunition, &backpack).join() {
+                    if in_pack.owner == entity && ammo.kind == *kind && ammo.count > 0 {
+                        ammo.count -= 1;
+                        if ammo.count < 1 {
+                            spent_ammo.push(ammo_entity);
+                        }
+                        found_ammo = true;
+                        break;
+                    }
+                }
+                if !found_ammo {
+                    log.entries
+                        .push(format!("{} has no {} left.", name.name, kind));
+                    continue;
+                }
+            } else {
+                // Thrown weapons leave the thrower's hand and land by the target
+                thrown_weapons.push((weapon_entity, Position { x: end.x, y: end.y }));
+            }
+
+            // Draw the projectile's flight; later tiles live longer, so the trail fades
+            // from the shooter towards the target
+            let glyph = projectile_glyph(start, end);
+            let path = rltk::line2d(LineAlg::Bresenham, start, end);
+            for (i, point) in path.iter().filter(|p| **p != start).enumerate() {
+                add_effect(
+                    None,
+                    EffectType::Particle {
+                        glyph,
+                        fg: rltk::RGB::named(rltk::WHITE),
+                        bg: rltk::RGB::named(rltk::BLACK),
+                        lifespan: 40.0 * (i + 1) as f32,
+                    },
+                    Targets::Tile {
+                        tile_idx: map.xy_idx(point.x, point.y) as i32,
+                    },
+                );
+            }
+
+            let attribute_bonus = match weapon_info.attribute {
+                WeaponAttribute::Might => attacker_attributes.might.bonus,
+                WeaponAttribute::Quickness => attacker_attributes.quickness.bonus,
+            };
+
+            // Roll to hit: d20 + weapon attribute + ranged skill + weapon bonus, against the
+            // target's armor class
+            let natural_roll = rng.roll_dice(1, 20);
+            let skill_hit_bonus = skill_bonus(Skill::Ranged, attacker_skills);
+            let mut status_hit_bonus = 0;
+            if let Some(hc) = hunger_clock.get(entity) {
+                // Well fed grants +1
+                if hc.state == HungerState::WellFed {
+                    status_hit_bonus += 1;
+                }
+            }
+            status_hit_bonus += status_hit_modifier(status_effects.get(entity));
+            let modified_hit_roll = natural_roll
+                + attribute_bonus
+                + skill_hit_bonus
+                + weapon_info.hit_bonus
+                + status_hit_bonus;
+
+            let base_armor_class = match natural_attacks.get(target) {
+                Some(nat) => nat.armor_class.unwrap_or(10),
+                None => 10,
+            };
+            let armor_quickness_bonus = target_attributes.quickness.bonus;
+            let armor_skill_bonus = skill_bonus(Skill::Defense, target_skills);
+            let mut armor_item_bonus_f = 0.0;
+            for (wearable, equipped_by) in (&wearables, &equipped).join() {
+                if equipped_by.owner == target {
+                    armor_item_bonus_f += wearable.armor_class;
+                }
+            }
+            let armor_class = base_armor_class
+                + armor_quickness_bonus
+                + armor_skill_bonus
+                + armor_item_bonus_f as i32;
+
+            let verb = if weapon_info.ammo.is_some() {
+                "shoots"
+            } else {
+                "throws"
+            };
+            if natural_roll != 1 && (natural_roll == 20 || modified_hit_roll > armor_class) {
+                let base_damage =
+                    rng.roll_dice(weapon_info.damage_n_dice, weapon_info.damage_die_type);
+                let skill_damage_bonus = skill_bonus(Skill::Ranged, attacker_skills);
+                let damage = i32::max(
+                    0,
+                    base_damage + attribute_bonus + skill_damage_bonus + weapon_info.damage_bonus,
+                );
+
+                if damage == 0 {
+                    log.entries.push(format!(
+                        "{} {} {} at {}, but it glances off.",
+                        name.name, verb, weapon_name, target_name.name
+                    ));
+                } else {
+                    log.entries.push(format!(
+                        "{} {} {} at {}, hitting for {} hp.",
+                        name.name, verb, weapon_name, target_name.name, damage
+                    ));
+                    add_effect(
+                        Some(entity),
+                        EffectType::Damage { amount: damage },
+                        Targets::Single { target },
+                    );
+                }
+                add_effect(
+                    None,
+                    EffectType::Particle {
+                        glyph: rltk::to_cp437('‼'),
+                        fg: rltk::RGB::named(rltk::ORANGE),
+                        bg: rltk::RGB::named(rltk::BLACK),
+                        lifespan: 200.0,
+                    },
+                    Targets::Single { target },
+                );
+            } else {
+                log.entries.push(format!(
+                    "{} {} {} at {}, but misses.",
+                    name.name, verb, weapon_name, target_name.name
+                ));
+                add_effect(
+                    None,
+                    EffectType::Particle {
+                        glyph: rltk::to_cp437('‼'),
+                        fg: rltk::RGB::named(rltk::CYAN),
+                        bg: rltk::RGB::named(rltk::BLACK),
+                        lifespan: 200.0,
+                    },
+                    Targets::Single { target },
+                );
+            }
+        }
+
+        for (weapon, landing) in thrown_weapons {
+            equipped.remove(weapon);
+            positions
+                .insert(weapon, landing)
+                .expect("Unable to insert position");
+        }
+        for ammo in spent_ammo {
+            entities.delete(ammo).expect("Delete failed");
+        }
+
+        wants_shoot.clear();
+    }
+}
@@ -0,0 +1,29 @@
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug)]
+pub struct DialogueRaws {
+    pub dialogues: Vec<DialogueTree>,
+}
+
+/// A conversation with an NPC. It opens at the node named by `start`, and each response
+/// either moves to another node, triggers an action, or ends the conversation.
+#[derive(Deserialize, Debug, Clone)]
+pub struct DialogueTree {
+    pub name: String,
+    pub start: String,
+    pub nodes: Vec<DialogueNode>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct DialogueNode {
+    pub id: String,
+    pub text: String,
+    pub responses: Vec<DialogueResponse>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct DialogueResponse {
+    pub text: String,
+    pub next: Option<String>,
+    pub action: Option<String>,
+}
@@ -0,0 +1,22 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+
+#[derive(Deserialize, Debug)]
+pub struct FactionRaws {
+    pub factions: Vec<FactionInfo>,
+}
+
+/// How members of a faction respond to each other faction. A "Default" entry covers anyone
+/// not listed.
+#[derive(Deserialize, Debug)]
+pub struct FactionInfo {
+    pub name: String,
+    pub responses: HashMap<String, String>,
+}
+
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug)]
+pub enum Reaction {
+    Ignore,
+    Attack,
+    Flee,
+}
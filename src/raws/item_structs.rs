@@ -7,7 +7,10 @@ pub struct Item {
     pub renderable: Option<Renderable>,
     pub consumable: Option<Consumable>,
     pub weapon: Option<Weapon>,
-    pub shield: Option<Shield>,
+    pub wearable: Option<Wearable>,
+    pub ammunition: Option<Ammunition>,
+    pub base_value: Option<f32>,
+    pub vendor_category: Option<String>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -26,10 +29,22 @@ pub struct Consumable {
 #[derive(Deserialize, Debug)]
 pub struct Weapon {
     pub range: String,
-    pub power_bonus: i32,
+    pub attribute: String,
+    pub base_damage: String,
+    pub hit_bonus: i32,
+    pub max_range: Option<i32>,
+    pub ammo: Option<String>,
 }
 
 #[derive(Deserialize, Debug)]
-pub struct Shield {
-    pub defense_bonus: i32,
+pub struct Ammunition {
+    pub kind: String,
+    pub count: i32,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Wearable {
+    pub armor_class: f32,
+    pub slot: String,
+    pub weight_lbs: Option<f32>,
 }
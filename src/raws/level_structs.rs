@@ -0,0 +1,42 @@
+use serde::Deserialize;
+
+#[derive(Deserialize, Debug)]
+pub struct LevelRaws {
+    pub level_chains: Vec<LevelChain>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct LevelChain {
+    pub name: String,
+    pub weight: i32,
+    pub min_depth: i32,
+    pub max_depth: i32,
+    pub add_map_depth_to_weight: Option<bool>,
+    pub starter: BuilderStep,
+    pub builders: Vec<BuilderStep>,
+}
+
+/// One entry in a chain. It either names a builder (plus that builder's parameters), picks
+/// one of several weighted options, or groups a sequence of steps. `chance` makes any step
+/// optional.
+#[derive(Deserialize, Debug, Clone)]
+pub struct BuilderStep {
+    pub name: Option<String>,
+    pub one_of: Option<Vec<BuilderStep>>,
+    pub sequence: Option<Vec<BuilderStep>>,
+    pub weight: Option<i32>,
+    pub chance: Option<f32>,
+
+    pub preset: Option<String>,
+    pub template: Option<String>,
+    pub algorithm: Option<String>,
+    pub spawn_mode: Option<String>,
+    pub lifetime: Option<i32>,
+    pub floor_percent: Option<f32>,
+    pub brush_size: Option<i32>,
+    pub symmetry: Option<String>,
+    pub seeds: Option<usize>,
+    pub sort: Option<String>,
+    pub x_start: Option<String>,
+    pub y_start: Option<String>,
+}
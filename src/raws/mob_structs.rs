@@ -1,20 +1,49 @@
 use super::Renderable;
 use serde::Deserialize;
+use std::collections::HashMap;
 
 #[derive(Deserialize, Debug)]
 pub struct Mob {
     pub name: String,
     pub renderable: Option<Renderable>,
     pub blocks_tile: bool,
-    pub stats: MobStats,
     pub vision_range: i32,
-    pub ai: String,
+    pub faction: Option<String>,
+    pub movement: Option<String>,
+    pub attributes: MobAttributes,
+    pub skills: Option<HashMap<String, i32>>,
+    pub level: Option<i32>,
+    pub hp: Option<i32>,
+    pub natural: Option<MobNatural>,
+    pub awareness: Option<MobAwareness>,
+    pub vendor: Option<Vec<String>>,
+    pub quips: Option<Vec<String>>,
+    pub dialogue: Option<String>,
 }
 
 #[derive(Deserialize, Debug)]
-pub struct MobStats {
-    pub max_hp: i32,
-    pub hp: i32,
-    pub power: i32,
-    pub defense: i32,
+pub struct MobAwareness {
+    pub search_turns: Option<i32>,
+    pub alert_radius: Option<i32>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct MobNatural {
+    pub armor_class: Option<i32>,
+    pub attacks: Option<Vec<NaturalAttack>>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct NaturalAttack {
+    pub name: String,
+    pub hit_bonus: i32,
+    pub damage: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct MobAttributes {
+    pub might: Option<i32>,
+    pub fitness: Option<i32>,
+    pub quickness: Option<i32>,
+    pub intelligence: Option<i32>,
 }
@@ -6,12 +6,21 @@ mod prop_structs;
 use prop_structs::*;
 mod spawn_table_structs;
 use spawn_table_structs::*;
+mod level_structs;
+pub use level_structs::*;
+mod faction_structs;
+pub use faction_structs::*;
+mod dialogue_structs;
+pub use dialogue_structs::*;
 mod rawmaster;
 pub use rawmaster::*;
 use serde::Deserialize;
 use std::sync::Mutex;
 
 rltk::embedded_resource!(RAW_FILE, "../../raws/spawns.json");
+rltk::embedded_resource!(LEVEL_FILE, "../../raws/levels.json");
+rltk::embedded_resource!(FACTION_FILE, "../../raws/factions.json");
+rltk::embedded_resource!(DIALOGUE_FILE, "../../raws/dialogue.json");
 
 lazy_static! {
     pub static ref RAWS: Mutex<RawMaster> = Mutex::new(RawMaster::empty());
@@ -23,6 +32,12 @@ pub struct Raws {
     pub mobs: Vec<Mob>,
     pub props: Vec<Prop>,
     pub spawn_table: Vec<SpawnTableEntry>,
+    #[serde(default)]
+    pub level_chains: Vec<LevelChain>,
+    #[serde(default)]
+    pub factions: Vec<FactionInfo>,
+    #[serde(default)]
+    pub dialogues: Vec<DialogueTree>,
 }
 
 pub fn load_raws() {
@@ -35,7 +50,41 @@ pub fn load_raws() {
         .unwrap();
     let raw_string =
         std::str::from_utf8(&raw_data).expect("Unable to convert to a valid UTF-8 string.");
-    let decoder: Raws = serde_json::from_str(&raw_string).expect("Unable to parse JSON");
+    let mut decoder: Raws = serde_json::from_str(&raw_string).expect("Unable to parse JSON");
+
+    // Level layouts live in their own file
+    rltk::link_resource!(LEVEL_FILE, "../../raws/levels.json");
+    let level_data = rltk::embedding::EMBED
+        .lock()
+        .get_resource("../../raws/levels.json".to_string())
+        .unwrap();
+    let level_string =
+        std::str::from_utf8(level_data).expect("Unable to convert to a valid UTF-8 string.");
+    let levels: LevelRaws = serde_json::from_str(level_string).expect("Unable to parse JSON");
+    decoder.level_chains = levels.level_chains;
+
+    // As do faction relationships
+    rltk::link_resource!(FACTION_FILE, "../../raws/factions.json");
+    let faction_data = rltk::embedding::EMBED
+        .lock()
+        .get_resource("../../raws/factions.json".to_string())
+        .unwrap();
+    let faction_string =
+        std::str::from_utf8(faction_data).expect("Unable to convert to a valid UTF-8 string.");
+    let factions: FactionRaws = serde_json::from_str(faction_string).expect("Unable to parse JSON");
+    decoder.factions = factions.factions;
+
+    // And conversations
+    rltk::link_resource!(DIALOGUE_FILE, "../../raws/dialogue.json");
+    let dialogue_data = rltk::embedding::EMBED
+        .lock()
+        .get_resource("../../raws/dialogue.json".to_string())
+        .unwrap();
+    let dialogue_string =
+        std::str::from_utf8(dialogue_data).expect("Unable to convert to a valid UTF-8 string.");
+    let dialogues: DialogueRaws =
+        serde_json::from_str(dialogue_string).expect("Unable to parse JSON");
+    decoder.dialogues = dialogues.dialogues;
 
     RAWS.lock().unwrap().load(decoder);
 }
@@ -1,11 +1,14 @@
-use super::Raws;
+use super::{DialogueNode, Raws, Reaction};
 use crate::components::*;
+use crate::gamesystem::npc_hp;
+use crate::map_builders::BuilderRegistry;
 use crate::random_table::RandomTable;
 use specs::prelude::*;
 use std::collections::{HashMap, HashSet};
 
 pub enum SpawnType {
     AtPosition { x: i32, y: i32 },
+    Carried { by: Entity },
 }
 
 pub struct RawMaster {
@@ -13,6 +16,9 @@ pub struct RawMaster {
     item_index: HashMap<String, usize>,
     mob_index: HashMap<String, usize>,
     prop_index: HashMap<String, usize>,
+    level_chain_index: HashMap<String, usize>,
+    faction_index: HashMap<String, HashMap<String, Reaction>>,
+    dialogue_index: HashMap<String, usize>,
 }
 
 impl RawMaster {
@@ -23,10 +29,16 @@ impl RawMaster {
                 mobs: Vec::new(),
                 props: Vec::new(),
                 spawn_table: Vec::new(),
+                level_chains: Vec::new(),
+                factions: Vec::new(),
+                dialogues: Vec::new(),
             },
             item_index: HashMap::new(),
             mob_index: HashMap::new(),
             prop_index: HashMap::new(),
+            level_chain_index: HashMap::new(),
+            faction_index: HashMap::new(),
+            dialogue_index: HashMap::new(),
         }
     }
 
@@ -41,6 +53,67 @@ impl RawMaster {
                     item.name
                 ));
             }
+            if let Some(consumable) = &item.consumable {
+                for (effect, value) in consumable.effects.iter() {
+                    let is_status = matches!(
+                        effect.as_str(),
+                        "poison" | "regenerate" | "slow" | "haste" | "stun" | "blind" | "confusion"
+                    );
+                    if is_status {
+                        if let Err(e) = parse_status_string(effect, value) {
+                            rltk::console::log(format!(
+                                "WARNING - item [{}] has a bad {} effect: {}",
+                                item.name, effect, e
+                            ));
+                        }
+                    }
+                }
+            }
+            if let Some(weapon) = &item.weapon {
+                if let Err(e) = parse_dice_string(&weapon.base_damage) {
+                    rltk::console::log(format!(
+                        "WARNING - item [{}] has bad damage dice: {}",
+                        item.name, e
+                    ));
+                }
+                if string_to_weapon_attribute(&weapon.attribute).is_none() {
+                    rltk::console::log(format!(
+                        "WARNING - item [{}] has unknown weapon attribute [{}]",
+                        item.name, weapon.attribute
+                    ));
+                }
+                match weapon.range.as_str() {
+                    "melee" => {}
+                    "ranged" | "thrown" => {
+                        if weapon.max_range.is_none() {
+                            rltk::console::log(format!(
+                                "WARNING - {} weapon [{}] has no max_range",
+                                weapon.range, item.name
+                            ));
+                        }
+                        if weapon.range == "ranged" && weapon.ammo.is_none() {
+                            rltk::console::log(format!(
+                                "WARNING - ranged weapon [{}] has no ammo",
+                                item.name
+                            ));
+                        }
+                    }
+                    _ => {
+                        rltk::console::log(format!(
+                            "WARNING - item [{}] has unknown weapon range [{}]",
+                            item.name, weapon.range
+                        ));
+                    }
+                }
+            }
+            if let Some(wearable) = &item.wearable {
+                if string_to_slot(&wearable.slot).is_none() {
+                    rltk::console::log(format!(
+                        "WARNING - unknown equipment slot [{}] on item [{}]",
+                        wearable.slot, item.name
+                    ));
+                }
+            }
             self.item_index.insert(item.name.clone(), i);
             used_names.insert(item.name.clone());
         }
@@ -51,6 +124,37 @@ impl RawMaster {
                     mob.name
                 ));
             }
+            if let Some(movement) = &mob.movement {
+                if !["static", "random", "random_waypoint"].contains(&movement.as_str()) {
+                    rltk::console::log(format!(
+                        "WARNING - mob [{}] has unknown movement [{}]",
+                        mob.name, movement
+                    ));
+                }
+            }
+            for category in mob.vendor.iter().flatten() {
+                let stocked = self
+                    .raws
+                    .items
+                    .iter()
+                    .any(|item| item.vendor_category.as_ref() == Some(category));
+                if !stocked {
+                    rltk::console::log(format!(
+                        "WARNING - vendor [{}] sells category [{}], but no item has it",
+                        mob.name, category
+                    ));
+                }
+            }
+            if let Some(natural) = &mob.natural {
+                for attack in natural.attacks.iter().flatten() {
+                    if let Err(e) = parse_dice_string(&attack.damage) {
+                        rltk::console::log(format!(
+                            "WARNING - mob [{}] attack [{}] has bad damage dice: {}",
+                            mob.name, attack.name, e
+                        ));
+                    }
+                }
+            }
             self.mob_index.insert(mob.name.clone(), i);
             used_names.insert(mob.name.clone());
         }
@@ -72,6 +176,178 @@ impl RawMaster {
                 ));
             }
         }
+
+        self.level_chain_index = HashMap::new();
+        let registry = BuilderRegistry::new();
+        for (i, chain) in self.raws.level_chains.iter().enumerate() {
+            if self.level_chain_index.contains_key(&chain.name) {
+                rltk::console::log(format!(
+                    "WARNING - duplicate level chain name in raws [{}]",
+                    chain.name
+                ));
+            }
+            for error in registry.validate_chain(chain) {
+                rltk::console::log(format!("WARNING - level chain [{}]: {}", chain.name, error));
+            }
+            self.level_chain_index.insert(chain.name.clone(), i);
+        }
+
+        self.faction_index = HashMap::new();
+        for faction in self.raws.factions.iter() {
+            let mut reactions: HashMap<String, Reaction> = HashMap::new();
+            for (other, response) in faction.responses.iter() {
+                let reaction = match response.as_str() {
+                    "ignore" => Reaction::Ignore,
+                    "flee" => Reaction::Flee,
+                    "attack" => Reaction::Attack,
+                    _ => {
+                        rltk::console::log(format!(
+                            "WARNING - faction [{}] has unknown response [{}] to [{}]",
+                            faction.name, response, other
+                        ));
+                        Reaction::Ignore
+                    }
+                };
+                reactions.insert(other.clone(), reaction);
+            }
+            self.faction_index.insert(faction.name.clone(), reactions);
+        }
+        self.dialogue_index = HashMap::new();
+        for (i, tree) in self.raws.dialogues.iter().enumerate() {
+            let ids: HashSet<&String> = tree.nodes.iter().map(|n| &n.id).collect();
+            if !ids.contains(&tree.start) {
+                rltk::console::log(format!(
+                    "WARNING - dialogue [{}] starts at unknown node [{}]",
+                    tree.name, tree.start
+                ));
+            }
+            for node in tree.nodes.iter() {
+                for response in node.responses.iter() {
+                    if let Some(next) = &response.next {
+                        if !ids.contains(next) {
+                            rltk::console::log(format!(
+                                "WARNING - dialogue [{}] node [{}] leads to unknown node [{}]",
+                                tree.name, node.id, next
+                            ));
+                        }
+                    }
+                    if let Some(action) = &response.action {
+                        if action != "trade" {
+                            rltk::console::log(format!(
+                                "WARNING - dialogue [{}] node [{}] has unknown action [{}]",
+                                tree.name, node.id, action
+                            ));
+                        }
+                    }
+                }
+            }
+            self.dialogue_index.insert(tree.name.clone(), i);
+        }
+
+        for mob in self.raws.mobs.iter() {
+            if let Some(dialogue) = &mob.dialogue {
+                if !self.dialogue_index.contains_key(dialogue) {
+                    rltk::console::log(format!(
+                        "WARNING - mob [{}] has unknown dialogue [{}]",
+                        mob.name, dialogue
+                    ));
+                }
+            }
+            if let Some(faction) = &mob.faction {
+                if !self.faction_index.contains_key(faction) {
+                    rltk::console::log(format!(
+                        "WARNING - mob [{}] belongs to unknown faction [{}]",
+                        mob.name, faction
+                    ));
+                }
+            }
+        }
+    }
+}
+
+/// Parses dice notation such as "1d8", "2d4+1" or "1d6-1" into (dice, die type, bonus).
+pub fn parse_dice_string(dice: &str) -> Result<(i32, i32, i32), String> {
+    let dice = dice.trim();
+    let (n_dice, rest) = dice
+        .split_once('d')
+        .ok_or_else(|| format!("[{}] is not in NdS+B form", dice))?;
+    let (die_type, bonus) = match rest.find(['+', '-']) {
+        Some(idx) => (&rest[..idx], &rest[idx..]),
+        None => (rest, "0"),
+    };
+    let n_dice = n_dice
+        .parse::<i32>()
+        .map_err(|_| format!("[{}] has a bad number of dice", dice))?;
+    let die_type = die_type
+        .parse::<i32>()
+        .map_err(|_| format!("[{}] has a bad die type", dice))?;
+    let bonus = bonus
+        .trim_start_matches('+')
+        .parse::<i32>()
+        .map_err(|_| format!("[{}] has a bad bonus", dice))?;
+    if n_dice < 1 || die_type < 1 {
+        return Err(format!("[{}] must roll at least one die of one side", dice));
+    }
+    Ok((n_dice, die_type, bonus))
+}
+
+/// Parses a status effect from the raws. Poison and regeneration take "NdS over T" (the dice
+/// are the total dealt over T turns); the other statuses just take a number of turns.
+pub fn parse_status_string(effect: &str, value: &str) -> Result<StatusSpec, String> {
+    let kind = match effect {
+        "poison" => StatusKind::Poisoned,
+        "regenerate" => StatusKind::Regenerating,
+        "slow" => StatusKind::Slowed,
+        "haste" => StatusKind::Hasted,
+        "stun" => StatusKind::Stunned,
+        "blind" => StatusKind::Blinded,
+        "confusion" => StatusKind::Confused,
+        _ => return Err(format!("[{}] is not a status effect", effect)),
+    };
+    let (turns, (n_dice, die_type, bonus)) = if kind.stacks() {
+        let (dice, turns) = value
+            .split_once(" over ")
+            .ok_or_else(|| format!("[{}] is not in \"NdS over T\" form", value))?;
+        (turns, parse_dice_string(dice)?)
+    } else {
+        (value, (0, 0, 0))
+    };
+    let turns = turns
+        .trim()
+        .parse::<i32>()
+        .map_err(|_| format!("[{}] has a bad number of turns", value))?;
+    if turns < 1 {
+        return Err(format!("[{}] must last at least one turn", value));
+    }
+    Ok(StatusSpec {
+        kind,
+        turns,
+        n_dice,
+        die_type,
+        bonus,
+    })
+}
+
+pub fn string_to_weapon_attribute(attribute: &str) -> Option<WeaponAttribute> {
+    match attribute {
+        "Might" => Some(WeaponAttribute::Might),
+        "Quickness" => Some(WeaponAttribute::Quickness),
+        _ => None,
+    }
+}
+
+pub fn string_to_slot(slot: &str) -> Option<EquipmentSlot> {
+    match slot {
+        "Shield" => Some(EquipmentSlot::Shield),
+        "Head" => Some(EquipmentSlot::Head),
+        "Torso" => Some(EquipmentSlot::Torso),
+        "Legs" => Some(EquipmentSlot::Legs),
+        "Feet" => Some(EquipmentSlot::Feet),
+        "Hands" => Some(EquipmentSlot::Hands),
+        "Ring" => Some(EquipmentSlot::Ring),
+        "Amulet" => Some(EquipmentSlot::Amulet),
+        "Melee" => Some(EquipmentSlot::Melee),
+        _ => None,
     }
 }
 
@@ -83,6 +359,9 @@ fn spawn_position(pos: SpawnType, new_entity: EntityBuilder) -> EntityBuilder {
         SpawnType::AtPosition { x, y } => {
             eb = eb.with(Position { x, y });
         }
+        SpawnType::Carried { by } => {
+            eb = eb.with(InBackpack { owner: by });
+        }
     }
 
     eb
@@ -122,10 +401,13 @@ pub fn spawn_named_item(
             name: item_template.name.clone(),
         });
 
-        eb = eb.with(crate::components::Item {});
+        eb = eb.with(crate::components::Item {
+            base_value: item_template.base_value.unwrap_or(0.0),
+        });
 
         if let Some(consumable) = &item_template.consumable {
             eb = eb.with(crate::components::Consumable {});
+            let mut statuses = Vec::new();
             for effect in consumable.effects.iter() {
                 let effect_name = effect.0.as_str();
                 match effect_name {
@@ -149,12 +431,14 @@ pub fn spawn_named_item(
                             radius: effect.1.parse::<i32>().unwrap(),
                         })
                     }
-                    "confusion" => {
-                        eb = eb.with(Confusion {
-                            turns: effect.1.parse::<i32>().unwrap(),
-                        })
+                    "poison" | "regenerate" | "slow" | "haste" | "stun" | "blind" | "confusion" => {
+                        // Malformed statuses are reported when the raws load
+                        if let Ok(spec) = parse_status_string(effect_name, effect.1) {
+                            statuses.push(spec);
+                        }
                     }
                     "magic_mapping" => eb = eb.with(MagicMapper {}),
+                    "blink" => eb = eb.with(Blink {}),
                     "food" => eb = eb.with(ProvidesFood {}),
                     _ => {
                         rltk::console::log(format!(
@@ -164,26 +448,58 @@ pub fn spawn_named_item(
                     }
                 }
             }
+            if !statuses.is_empty() {
+                eb = eb.with(InflictsStatus { statuses });
+            }
         }
 
         if let Some(weapon) = &item_template.weapon {
             eb = eb.with(Equippable {
                 slot: EquipmentSlot::Melee,
             });
-            eb = eb.with(MeleePowerBonus {
-                power: weapon.power_bonus,
-            });
+            // Bad dice are reported when the raws load; fall back to a plain 1d4 here
+            let (n_dice, die_type, bonus) =
+                parse_dice_string(&weapon.base_damage).unwrap_or((1, 4, 0));
+            let attribute =
+                string_to_weapon_attribute(&weapon.attribute).unwrap_or(WeaponAttribute::Might);
+            if weapon.range == "melee" {
+                eb = eb.with(MeleeWeapon {
+                    attribute,
+                    damage_n_dice: n_dice,
+                    damage_die_type: die_type,
+                    damage_bonus: bonus,
+                    hit_bonus: weapon.hit_bonus,
+                });
+            } else {
+                eb = eb.with(RangedWeapon {
+                    attribute,
+                    damage_n_dice: n_dice,
+                    damage_die_type: die_type,
+                    damage_bonus: bonus,
+                    hit_bonus: weapon.hit_bonus,
+                    range: weapon.max_range.unwrap_or(1),
+                    ammo: weapon.ammo.clone(),
+                });
+            }
         }
 
-        if let Some(shield) = &item_template.shield {
-            eb = eb.with(Equippable {
-                slot: EquipmentSlot::Shield,
-            });
-            eb = eb.with(DefenseBonus {
-                defense: shield.defense_bonus,
+        if let Some(ammunition) = &item_template.ammunition {
+            eb = eb.with(Ammunition {
+                kind: ammunition.kind.clone(),
+                count: ammunition.count,
             });
         }
 
+        if let Some(wearable) = &item_template.wearable {
+            if let Some(slot) = string_to_slot(&wearable.slot) {
+                eb = eb.with(Equippable { slot });
+                eb = eb.with(Wearable {
+                    armor_class: wearable.armor_class,
+                    weight_lbs: wearable.weight_lbs.unwrap_or(0.0),
+                });
+            }
+        }
+
         return Some(eb.build());
     }
     None
@@ -212,19 +528,109 @@ pub fn spawn_named_mob(
             name: mob_template.name.clone(),
         });
 
-        match mob_template.ai.as_ref() {
-            "melee" => eb = eb.with(Monster {}),
-            "bystander" => eb = eb.with(Bystander {}),
-            _ => {}
-        }
+        eb = eb.with(Faction {
+            name: mob_template
+                .faction
+                .clone()
+                .unwrap_or_else(|| "Mindless".to_string()),
+        });
+        let mode = match mob_template.movement.as_deref() {
+            Some("random") => Movement::Random,
+            Some("random_waypoint") => Movement::RandomWaypoint { path: None },
+            _ => Movement::Static,
+        };
+        eb = eb.with(MoveMode { mode });
         if mob_template.blocks_tile {
             eb = eb.with(BlocksTile {});
         }
-        eb = eb.with(CombatStats {
-            max_hp: mob_template.stats.max_hp,
-            hp: mob_template.stats.hp,
-            power: mob_template.stats.power,
-            defense: mob_template.stats.defense,
+        let mob_attribute = |value: Option<i32>| Attribute::new(value.unwrap_or(11));
+        let attributes = Attributes {
+            might: mob_attribute(mob_template.attributes.might),
+            fitness: mob_attribute(mob_template.attributes.fitness),
+            quickness: mob_attribute(mob_template.attributes.quickness),
+            intelligence: mob_attribute(mob_template.attributes.intelligence),
+        };
+
+        let mut skills = Skills {
+            skills: HashMap::new(),
+        };
+        skills.skills.insert(Skill::Melee, 1);
+        skills.skills.insert(Skill::Defense, 1);
+        skills.skills.insert(Skill::Magic, 1);
+        skills.skills.insert(Skill::Ranged, 1);
+        if let Some(mobskills) = &mob_template.skills {
+            for sk in mobskills.iter() {
+                match sk.0.as_str() {
+                    "Melee" => {
+                        skills.skills.insert(Skill::Melee, *sk.1);
+                    }
+                    "Defense" => {
+                        skills.skills.insert(Skill::Defense, *sk.1);
+                    }
+                    "Magic" => {
+                        skills.skills.insert(Skill::Magic, *sk.1);
+                    }
+                    "Ranged" => {
+                        skills.skills.insert(Skill::Ranged, *sk.1);
+                    }
+                    _ => {
+                        rltk::console::log(format!("Unknown skill referenced: [{}]", sk.0));
+                    }
+                }
+            }
+        }
+
+        let mob_level = mob_template.level.unwrap_or(1);
+        let mob_hp = mob_template
+            .hp
+            .unwrap_or_else(|| npc_hp(attributes.fitness.base, mob_level));
+        eb = eb.with(Pools {
+            hit_points: Pool {
+                current: mob_hp,
+                max: mob_hp,
+            },
+            xp: 0,
+            level: mob_level,
+            gold: 0.0,
+        });
+        if let Some(categories) = &mob_template.vendor {
+            eb = eb.with(Vendor {
+                categories: categories.clone(),
+            });
+        }
+        if let Some(quips) = &mob_template.quips {
+            eb = eb.with(Quips {
+                available: quips.clone(),
+            });
+        }
+        if let Some(tree) = &mob_template.dialogue {
+            eb = eb.with(Dialogue { tree: tree.clone() });
+        }
+        if let Some(natural) = &mob_template.natural {
+            let mut nature = NaturalAttackDefense {
+                armor_class: natural.armor_class,
+                attacks: Vec::new(),
+            };
+            for attack in natural.attacks.iter().flatten() {
+                let (n_dice, die_type, bonus) =
+                    parse_dice_string(&attack.damage).unwrap_or((1, 4, 0));
+                nature.attacks.push(NaturalAttack {
+                    name: attack.name.clone(),
+                    damage_n_dice: n_dice,
+                    damage_die_type: die_type,
+                    damage_bonus: bonus,
+                    hit_bonus: attack.hit_bonus,
+                });
+            }
+            eb = eb.with(nature);
+        }
+        eb = eb.with(attributes);
+        eb = eb.with(skills);
+        eb = eb.with(Initiative { current: 2 });
+        let awareness = mob_template.awareness.as_ref();
+        eb = eb.with(Awareness {
+            search_turns: awareness.and_then(|a| a.search_turns).unwrap_or(5),
+            alert_radius: awareness.and_then(|a| a.alert_radius),
         });
         eb = eb.with(Viewshed {
             visible_tiles: Vec::new(),
@@ -335,3 +741,78 @@ pub fn get_spawn_table_for_depth(raws: &RawMaster, depth: i32) -> RandomTable {
     }
     rt
 }
+
+pub fn get_level_chain_table(raws: &RawMaster, depth: i32) -> RandomTable {
+    let mut rt = RandomTable::new();
+    for chain in raws
+        .raws
+        .level_chains
+        .iter()
+        .filter(|c| depth >= c.min_depth && depth <= c.max_depth)
+    {
+        let mut weight = chain.weight;
+        if chain.add_map_depth_to_weight.is_some() {
+            weight += depth;
+        }
+        rt = rt.add(chain.name.clone(), weight);
+    }
+    rt
+}
+
+pub fn get_level_chain(raws: &RawMaster, name: &str) -> Option<super::LevelChain> {
+    raws.level_chain_index
+        .get(name)
+        .map(|idx| raws.raws.level_chains[*idx].clone())
+}
+
+pub fn level_chain_names(raws: &RawMaster) -> Vec<String> {
+    raws.raws
+        .level_chains
+        .iter()
+        .map(|c| c.name.clone())
+        .collect()
+}
+
+/// How a member of `my_faction` feels about a member of `their_faction`. Unlisted pairs fall
+/// back to the faction's "Default" response, and unknown factions are ignored.
+pub fn faction_reaction(my_faction: &str, their_faction: &str, raws: &RawMaster) -> Reaction {
+    if let Some(reactions) = raws.faction_index.get(my_faction) {
+        if let Some(reaction) = reactions.get(their_faction) {
+            return *reaction;
+        } else if let Some(reaction) = reactions.get("Default") {
+            return *reaction;
+        }
+    }
+    Reaction::Ignore
+}
+
+/// Everything a vendor dealing in `categories` has for sale, with prices.
+pub fn get_vendor_items(categories: &[String], raws: &RawMaster) -> Vec<(String, f32)> {
+    raws.raws
+        .items
+        .iter()
+        .filter(|item| {
+            item.vendor_category
+                .as_ref()
+                .is_some_and(|cat| categories.contains(cat))
+        })
+        .map(|item| (item.name.clone(), item.base_value.unwrap_or(0.0)))
+        .collect()
+}
+
+/// Where a conversation with the named dialogue tree begins.
+pub fn dialogue_start(raws: &RawMaster, tree: &str) -> Option<usize> {
+    let tree = &raws.raws.dialogues[*raws.dialogue_index.get(tree)?];
+    tree.nodes.iter().position(|n| n.id == tree.start)
+}
+
+pub fn dialogue_node(raws: &RawMaster, tree: &str, node: usize) -> Option<DialogueNode> {
+    let tree = &raws.raws.dialogues[*raws.dialogue_index.get(tree)?];
+    tree.nodes.get(node).cloned()
+}
+
+/// Finds a node in a dialogue tree by its id.
+pub fn dialogue_node_index(raws: &RawMaster, tree: &str, id: &str) -> Option<usize> {
+    let tree = &raws.raws.dialogues[*raws.dialogue_index.get(tree)?];
+    tree.nodes.iter().position(|n| n.id == id)
+}
@@ -0,0 +1,198 @@
+//! Where saved games are kept: files on native builds, the browser's `localStorage` on the
+//! web. Both hold exactly the same text, so `saveload_system` doesn't care which it's using.
+
+#[cfg(test)]
+use std::collections::HashMap;
+#[cfg(not(target_arch = "wasm32"))]
+use std::fs;
+#[cfg(all(not(target_arch = "wasm32"), feature = "compressed-saves"))]
+use std::io::{Read, Write};
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::PathBuf;
+
+pub trait SaveStorage {
+    /// Returns the named entry, or `None` if there isn't one.
+    fn read(&self, name: &str) -> Result<Option<String>, String>;
+    fn write(&mut self, name: &str, data: &str) -> Result<(), String>;
+    fn exists(&self, name: &str) -> bool;
+}
+
+/// The storage this platform saves to.
+pub fn default_storage() -> Box<dyn SaveStorage> {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        Box::new(FileStorage::new("./saves"))
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        Box::new(LocalStorage::new("rusty-roguelike"))
+    }
+}
+
+/// Seconds since the Unix epoch. `SystemTime` isn't available in the browser, so the web build
+/// asks JavaScript instead.
+pub fn unix_time() -> u64 {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |t| t.as_secs())
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        (js_sys::Date::now() / 1000.0) as u64
+    }
+}
+
+/// Keeps each entry as a `.json` file in a directory, or as `.json.gz` with the
+/// `compressed-saves` feature. Files in the other encoding are still read.
+#[cfg(not(target_arch = "wasm32"))]
+pub struct FileStorage {
+    dir: PathBuf,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl FileStorage {
+    pub fn new(dir: &str) -> FileStorage {
+        FileStorage {
+            dir: PathBuf::from(dir),
+        }
+    }
+
+    fn path(&self, name: &str, extension: &str) -> PathBuf {
+        self.dir.join(format!("{}.{}", name, extension))
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl SaveStorage for FileStorage {
+    fn read(&self, name: &str) -> Result<Option<String>, String> {
+        let compressed = self.path(name, "json.gz");
+        if compressed.exists() {
+            return read_compressed(&compressed).map(Some);
+        }
+        let plain = self.path(name, "json");
+        if !plain.exists() {
+            return Ok(None);
+        }
+        fs::read_to_string(plain)
+            .map(Some)
+            .map_err(|e| format!("Couldn't read the save file ({})", e))
+    }
+
+    fn write(&mut self, name: &str, data: &str) -> Result<(), String> {
+        fs::create_dir_all(&self.dir)
+            .map_err(|e| format!("Couldn't create the save directory ({})", e))?;
+        let (path, stale) = if cfg!(feature = "compressed-saves") {
+            (self.path(name, "json.gz"), self.path(name, "json"))
+        } else {
+            (self.path(name, "json"), self.path(name, "json.gz"))
+        };
+        write_file(&path, data).map_err(|e| format!("Couldn't write the save file ({})", e))?;
+        // Only the latest write should be left for `read` to find
+        let _ = fs::remove_file(stale);
+        Ok(())
+    }
+
+    fn exists(&self, name: &str) -> bool {
+        self.path(name, "json").exists() || self.path(name, "json.gz").exists()
+    }
+}
+
+#[cfg(all(not(target_arch = "wasm32"), not(feature = "compressed-saves")))]
+fn write_file(path: &std::path::Path, data: &str) -> std::io::Result<()> {
+    fs::write(path, data)
+}
+
+#[cfg(all(not(target_arch = "wasm32"), feature = "compressed-saves"))]
+fn write_file(path: &std::path::Path, data: &str) -> std::io::Result<()> {
+    let file = fs::File::create(path)?;
+    let mut writer = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+    writer.write_all(data.as_bytes())?;
+    writer.finish()?;
+    Ok(())
+}
+
+#[cfg(all(not(target_arch = "wasm32"), feature = "compressed-saves"))]
+fn read_compressed(path: &std::path::Path) -> Result<String, String> {
+    let file = fs::File::open(path).map_err(|e| format!("Couldn't read the save file ({})", e))?;
+    let mut data = String::new();
+    flate2::read::GzDecoder::new(file)
+        .read_to_string(&mut data)
+        .map_err(|e| format!("Couldn't decompress the save file ({})", e))?;
+    Ok(data)
+}
+
+#[cfg(all(not(target_arch = "wasm32"), not(feature = "compressed-saves")))]
+fn read_compressed(_path: &std::path::Path) -> Result<String, String> {
+    Err("This save is compressed, and this build doesn't support compressed saves".to_string())
+}
+
+/// Keeps each entry under `<prefix>/<name>` in the browser's `localStorage`.
+#[cfg(target_arch = "wasm32")]
+pub struct LocalStorage {
+    prefix: String,
+}
+
+#[cfg(target_arch = "wasm32")]
+impl LocalStorage {
+    pub fn new(prefix: &str) -> LocalStorage {
+        LocalStorage {
+            prefix: prefix.to_string(),
+        }
+    }
+
+    fn key(&self, name: &str) -> String {
+        format!("{}/{}", self.prefix, name)
+    }
+
+    fn storage() -> Result<web_sys::Storage, String> {
+        web_sys::window()
+            .ok_or("There's no browser window")?
+            .local_storage()
+            .map_err(|e| format!("Browser storage is unavailable ({:?})", e))?
+            .ok_or_else(|| "Browser storage is unavailable".to_string())
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl SaveStorage for LocalStorage {
+    fn read(&self, name: &str) -> Result<Option<String>, String> {
+        LocalStorage::storage()?
+            .get_item(&self.key(name))
+            .map_err(|e| format!("Couldn't read the save ({:?})", e))
+    }
+
+    fn write(&mut self, name: &str, data: &str) -> Result<(), String> {
+        LocalStorage::storage()?
+            .set_item(&self.key(name), data)
+            .map_err(|e| format!("Couldn't write the save ({:?})", e))
+    }
+
+    fn exists(&self, name: &str) -> bool {
+        matches!(self.read(name), Ok(Some(_)))
+    }
+}
+
+/// Keeps everything in memory, so tests can save and load without touching the disk.
+#[cfg(test)]
+#[derive(Default)]
+pub struct MemoryStorage {
+    entries: HashMap<String, String>,
+}
+
+#[cfg(test)]
+impl SaveStorage for MemoryStorage {
+    fn read(&self, name: &str) -> Result<Option<String>, String> {
+        Ok(self.entries.get(name).cloned())
+    }
+
+    fn write(&mut self, name: &str, data: &str) -> Result<(), String> {
+        self.entries.insert(name.to_string(), data.to_string());
+        Ok(())
+    }
+
+    fn exists(&self, name: &str) -> bool {
+        self.entries.contains_key(name)
+    }
+}
@@ -1,69 +1,100 @@
 use super::components::*;
+use super::save_storage::{default_storage, unix_time, SaveStorage};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
 use specs::error::NoError;
 use specs::prelude::*;
 use specs::saveload::{
     DeserializeComponents, MarkedBuilder, SerializeComponents, SimpleMarker, SimpleMarkerAllocator,
 };
-use std::fs;
-use std::fs::File;
-use std::path::Path;
+use std::collections::HashSet;
+use std::sync::Mutex;
 
-macro_rules! serialize_individually {
-    ($ecs:expr, $ser:expr, $data:expr, $( $type:ty),*) => {
-        $(
-        SerializeComponents::<NoError, SimpleMarker<SerializeMe>>::serialize(
-            &( $ecs.read_storage::<$type>(), ),
-            &$data.0,
-            &$data.1,
-            &mut $ser,
-        )
-        .unwrap();
-        )*
-    };
+/// How many save slots the menus offer.
+pub const SAVE_SLOTS: usize = 3;
+
+/// Bump this whenever the save layout changes, and add a migration from the previous version
+/// to `MIGRATIONS`.
+pub const SAVE_VERSION: u32 = 2;
+
+/// Upgrades a save from version `n + 1` to `n + 2`, so a save of any older version can be walked
+/// forward one step at a time.
+const MIGRATIONS: &[Migration] = &[migrate_v1_to_v2];
+
+type Migration = fn(Value) -> Result<Value, String>;
+
+/// A save file: a version header, then each component storage under its type name.
+#[derive(Serialize, Deserialize)]
+struct SaveFile {
+    version: u32,
+    components: Map<String, Value>,
 }
 
-#[cfg(target_arch = "wasm32")]
-pub fn save_game(_ecs: &mut World) {}
+/// A summary of a saved game, written beside it so the slot picker doesn't have to load the
+/// whole thing.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SaveMetadata {
+    pub level: i32,
+    pub depth: i32,
+    pub turns: u64,
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+    pub seed: u64,
+}
 
-#[cfg(not(target_arch = "wasm32"))]
-pub fn save_game(ecs: &mut World) {
-    // Create helper
-    let mapcopy = ecs.get_mut::<super::map::Map>().unwrap().clone();
-    let savehelper = ecs
-        .create_entity()
-        .with(SerializationHelper { map: mapcopy })
-        .marked::<SimpleMarker<SerializeMe>>()
-        .build();
+#[derive(Clone)]
+pub enum SaveSlot {
+    Empty,
+    Saved(SaveMetadata),
+    /// There's a save here, but its metadata is missing or unreadable
+    Unknown,
+}
 
-    // Actually serialize
-    {
-        let data = (
-            ecs.entities(),
-            ecs.read_storage::<SimpleMarker<SerializeMe>>(),
-        );
+fn slot_name(slot: usize) -> String {
+    format!("slot{}", slot + 1)
+}
 
-        let writer = File::create("./savegame.json").unwrap();
-        let mut serializer = serde_json::Serializer::new(writer);
-        serialize_individually!(
-            ecs,
-            serializer,
-            data,
+fn metadata_name(slot: usize) -> String {
+    format!("slot{}.meta", slot + 1)
+}
+
+/// Every component that is registered with the world and saved with the game, in save order.
+/// Registration, saving and loading all expand from this one list, so a new component only
+/// needs adding here.
+macro_rules! saved_components {
+    ($callback:ident $(, $args:expr)*) => {
+        $callback!(
+            $($args,)*
             Position,
             Renderable,
             Player,
             Viewshed,
-            Monster,
             Name,
             BlocksTile,
-            CombatStats,
-            SufferDamage,
+            Attributes,
+            Skills,
+            Pools,
             WantsToMelee,
             Item,
             Consumable,
             Ranged,
             InflictsDamage,
             AreaOfEffect,
-            Confusion,
+            StatusEffects,
+            InflictsStatus,
+            Blink,
+            Initiative,
+            MyTurn,
+            Faction,
+            Vendor,
+            Quips,
+            Dialogue,
+            MoveMode,
+            WantsToApproach,
+            WantsToFlee,
+            Chasing,
+            Searching,
+            Awareness,
             ProvidesHealing,
             InBackpack,
             WantsToPickupItem,
@@ -72,8 +103,12 @@ pub fn save_game(ecs: &mut World) {
             SerializationHelper,
             Equippable,
             Equipped,
-            MeleePowerBonus,
-            DefenseBonus,
+            MeleeWeapon,
+            Wearable,
+            NaturalAttackDefense,
+            RangedWeapon,
+            Ammunition,
+            WantsToShoot,
             WantsToRemoveItem,
             ParticleLifetime,
             HungerClock,
@@ -85,34 +120,525 @@ pub fn save_game(ecs: &mut World) {
             SingleActivation,
             BlocksVisibility,
             Door,
-            Bystander
+            OtherLevelPosition,
+            DMSerializationHelper
+        )
+    };
+}
+
+macro_rules! register_individually {
+    ($ecs:expr, $( $type:ty),*) => {
+        $(
+        $ecs.register::<$type>();
+        )*
+    };
+}
+
+macro_rules! component_names {
+    ($( $type:ty),*) => {
+        &[ $( stringify!($type) ),* ]
+    };
+}
+
+const SAVED_COMPONENTS: &[&str] = saved_components!(component_names);
+
+/// Registers every saved component, along with the marker that tags entities for saving.
+pub fn register_components(ecs: &mut World) {
+    saved_components!(register_individually, ecs);
+    ecs.register::<SimpleMarker<SerializeMe>>();
+    ecs.insert(SimpleMarkerAllocator::<SerializeMe>::new());
+}
+
+macro_rules! serialize_individually {
+    ($ecs:expr, $sections:expr, $data:expr, $( $type:ty),*) => {
+        $(
+        let mut buffer = Vec::new();
+        SerializeComponents::<NoError, SimpleMarker<SerializeMe>>::serialize(
+            &( $ecs.read_storage::<$type>(), ),
+            &$data.0,
+            &$data.1,
+            &mut serde_json::Serializer::new(&mut buffer),
+        )
+        .unwrap();
+        $sections.insert(
+            stringify!($type).to_string(),
+            serde_json::from_slice(&buffer).unwrap(),
         );
+        )*
+    };
+}
+
+/// Where the game kept its one save before there were slots.
+#[cfg(not(target_arch = "wasm32"))]
+const LEGACY_SAVE: &str = "./savegame.json";
+
+lazy_static! {
+    /// What's in each slot, read once and refreshed after every save, so the menus don't go to
+    /// storage every frame.
+    static ref SLOTS: Mutex<Option<Vec<SaveSlot>>> = Mutex::new(None);
+}
+
+/// Moves a save left by a version of the game that predates slots into a slot. Call this once,
+/// at startup.
+pub fn import_legacy_save() {
+    #[cfg(not(target_arch = "wasm32"))]
+    move_legacy_save(&mut *default_storage(), std::path::Path::new(LEGACY_SAVE));
+}
+
+/// Moves an old single-file save into the first empty slot. It's copied as it is, since old
+/// formats are upgraded when they're loaded. If every slot is taken it stays where it is.
+#[cfg(not(target_arch = "wasm32"))]
+fn move_legacy_save(storage: &mut dyn SaveStorage, path: &std::path::Path) {
+    if !path.exists() {
+        return;
+    }
+    let slot = match (0..SAVE_SLOTS).find(|slot| !storage.exists(&slot_name(*slot))) {
+        Some(slot) => slot,
+        None => return,
+    };
+    let imported = std::fs::read_to_string(path)
+        .map_err(|e| e.to_string())
+        .and_then(|data| storage.write(&slot_name(slot), &data));
+    match imported {
+        Ok(()) => {
+            let _ = std::fs::remove_file(path);
+        }
+        Err(e) => rltk::console::log(format!(
+            "WARNING - couldn't import the old save {}: {}",
+            path.display(),
+            e
+        )),
     }
+}
+
+pub fn save_game(ecs: &mut World, slot: usize) -> Result<(), String> {
+    let saved = save_game_to(&mut *default_storage(), ecs, slot);
+    *SLOTS.lock().unwrap() = None;
+    saved
+}
+
+fn save_game_to(storage: &mut dyn SaveStorage, ecs: &mut World, slot: usize) -> Result<(), String> {
+    let metadata = save_metadata(ecs);
+    let data = save_to_string(ecs);
+    storage.write(&slot_name(slot), &data)?;
+    storage.write(
+        &metadata_name(slot),
+        &serde_json::to_string(&metadata).unwrap(),
+    )
+}
+
+/// Serializes the game without touching the disk.
+fn save_to_string(ecs: &mut World) -> String {
+    serde_json::to_string(&serialize_world(ecs)).unwrap()
+}
+
+/// Snapshots every marked entity, along with the current map and dungeon record.
+fn serialize_world(ecs: &mut World) -> SaveFile {
+    // Create helper
+    let mapcopy = ecs.get_mut::<super::map::Map>().unwrap().clone();
+    let dungeon_master = ecs
+        .get_mut::<super::map::MasterDungeonMap>()
+        .unwrap()
+        .clone();
+    let savehelper = ecs
+        .create_entity()
+        .with(SerializationHelper { map: mapcopy })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build();
+    let savehelper2 = ecs
+        .create_entity()
+        .with(DMSerializationHelper {
+            map: dungeon_master,
+        })
+        .marked::<SimpleMarker<SerializeMe>>()
+        .build();
+
+    // Actually serialize
+    let components = {
+        let data = (
+            ecs.entities(),
+            ecs.read_storage::<SimpleMarker<SerializeMe>>(),
+        );
+
+        let mut components = Map::new();
+        saved_components!(serialize_individually, ecs, components, data);
+
+        components
+    };
 
     // Clean up
     ecs.delete_entity(savehelper).expect("Crash on cleanup");
+    ecs.delete_entity(savehelper2).expect("Crash on cleanup");
+
+    SaveFile {
+        version: SAVE_VERSION,
+        components,
+    }
+}
+
+fn save_metadata(ecs: &World) -> SaveMetadata {
+    let player = ecs.fetch::<Entity>();
+    let pools = ecs.read_storage::<Pools>();
+    let map = ecs.fetch::<super::map::Map>();
+    let dungeon_master = ecs.fetch::<super::map::MasterDungeonMap>();
+    SaveMetadata {
+        level: pools.get(*player).map_or(1, |p| p.level),
+        depth: map.depth,
+        turns: dungeon_master.turns,
+        timestamp: unix_time(),
+        seed: dungeon_master.seed,
+    }
 }
 
 pub fn does_save_exist() -> bool {
-    Path::new("./savegame.json").exists()
+    save_slots()
+        .iter()
+        .any(|slot| !matches!(slot, SaveSlot::Empty))
+}
+
+/// Describes what's in each save slot, for the slot picker.
+pub fn save_slots() -> Vec<SaveSlot> {
+    SLOTS
+        .lock()
+        .unwrap()
+        .get_or_insert_with(|| slots_in(&*default_storage()))
+        .clone()
+}
+
+fn slots_in(storage: &dyn SaveStorage) -> Vec<SaveSlot> {
+    (0..SAVE_SLOTS)
+        .map(|slot| {
+            if !storage.exists(&slot_name(slot)) {
+                return SaveSlot::Empty;
+            }
+            storage
+                .read(&metadata_name(slot))
+                .ok()
+                .flatten()
+                .and_then(|meta| serde_json::from_str(&meta).ok())
+                .map_or(SaveSlot::Unknown, SaveSlot::Saved)
+        })
+        .collect()
+}
+
+/// Component sections in the order the original, unversioned save format wrote them.
+const LEGACY_SECTIONS: &[&str] = &[
+    "Position",
+    "Renderable",
+    "Player",
+    "Viewshed",
+    "Monster",
+    "Name",
+    "BlocksTile",
+    "CombatStats",
+    "SufferDamage",
+    "WantsToMelee",
+    "Item",
+    "Consumable",
+    "Ranged",
+    "InflictsDamage",
+    "AreaOfEffect",
+    "Confusion",
+    "ProvidesHealing",
+    "InBackpack",
+    "WantsToPickupItem",
+    "WantsToUseItem",
+    "WantsToDropItem",
+    "SerializationHelper",
+    "Equippable",
+    "Equipped",
+    "MeleePowerBonus",
+    "DefenseBonus",
+    "WantsToRemoveItem",
+    "ParticleLifetime",
+    "HungerClock",
+    "ProvidesFood",
+    "MagicMapper",
+    "Hidden",
+    "EntryTrigger",
+    "EntityMoved",
+    "SingleActivation",
+    "BlocksVisibility",
+    "Door",
+    "Bystander",
+];
+
+/// The entities that have a component in a saved section, by marker id, with the component's
+/// data. Sections list every marked entity, with `null` for the ones that don't have it.
+fn section_entries(sections: &Map<String, Value>, name: &str) -> Vec<(u64, Value)> {
+    sections
+        .get(name)
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| {
+            let id = entry["marker"][0].as_u64()?;
+            let data = entry["components"].get(0).filter(|data| !data.is_null())?;
+            Some((id, data.clone()))
+        })
+        .collect()
+}
+
+fn section_from_entries(entries: Vec<(u64, Value)>) -> Value {
+    entries
+        .into_iter()
+        .map(|(id, data)| json!({ "components": [data], "marker": [id] }))
+        .collect()
+}
+
+/// Version 1 saves were a bare run of storages with no names, in `LEGACY_SECTIONS` order, from
+/// before the game had attributes, factions or a dungeon record. Components that were since
+/// replaced are translated into their successors with the defaults a fresh spawn would get;
+/// `SufferDamage` is mid-turn bookkeeping and is dropped.
+fn migrate_v1_to_v2(save: Value) -> Result<Value, String> {
+    let sections = match save {
+        Value::Array(sections) => sections,
+        _ => return Err("Expected a list of component sections".to_string()),
+    };
+    if sections.len() != LEGACY_SECTIONS.len() {
+        return Err(format!(
+            "Expected {} component sections but found {}",
+            LEGACY_SECTIONS.len(),
+            sections.len()
+        ));
+    }
+    let legacy: Map<String, Value> = LEGACY_SECTIONS
+        .iter()
+        .map(|name| name.to_string())
+        .zip(sections)
+        .collect();
+    let ids = |name: &str| -> HashSet<u64> {
+        section_entries(&legacy, name)
+            .into_iter()
+            .map(|(id, _)| id)
+            .collect()
+    };
+    let players = ids("Player");
+    let monsters = ids("Monster");
+    let bystanders = ids("Bystander");
+    let items = ids("Item");
+
+    let mut components = Map::new();
+    let mut add = |name: &str, entries: Vec<(u64, Value)>| {
+        components.insert(name.to_string(), section_from_entries(entries));
+    };
+
+    // Components that kept their shape carry straight over
+    for name in LEGACY_SECTIONS {
+        if *name != "Item" && SAVED_COMPONENTS.contains(name) {
+            add(name, section_entries(&legacy, name));
+        }
+    }
+    add(
+        "Item",
+        section_entries(&legacy, "Item")
+            .into_iter()
+            .map(|(id, _)| (id, json!({ "base_value": 0.0 })))
+            .collect(),
+    );
+
+    // Combat stats become pools, with everything else an untrained level 1 creature has
+    let combatants = section_entries(&legacy, "CombatStats");
+    let attribute = serde_json::to_value(Attribute::new(11)).map_err(|e| e.to_string())?;
+    let with_each = |data: Value| -> Vec<(u64, Value)> {
+        combatants
+            .iter()
+            .map(|(id, _)| (*id, data.clone()))
+            .collect()
+    };
+    add(
+        "Pools",
+        combatants
+            .iter()
+            .map(|(id, stats)| {
+                let pools = json!({
+                    "hit_points": { "current": stats["hp"], "max": stats["max_hp"] },
+                    "xp": 0,
+                    "level": 1,
+                    "gold": 0.0,
+                });
+                (*id, pools)
+            })
+            .collect(),
+    );
+    add(
+        "Attributes",
+        with_each(json!({
+            "might": attribute,
+            "fitness": attribute,
+            "quickness": attribute,
+            "intelligence": attribute,
+        })),
+    );
+    add(
+        "Skills",
+        with_each(json!({ "skills": { "Melee": 1, "Defense": 1, "Magic": 1, "Ranged": 1 } })),
+    );
+
+    // Monsters and bystanders were told apart by marker components; now it's by faction
+    let creatures = |ids: &HashSet<u64>, data: Value| -> Vec<(u64, Value)> {
+        let mut ids: Vec<u64> = ids.iter().copied().collect();
+        ids.sort_unstable();
+        ids.into_iter().map(|id| (id, data.clone())).collect()
+    };
+    let mut factions = creatures(&players, json!({ "name": "Player" }));
+    factions.extend(creatures(&monsters, json!({ "name": "Mindless" })));
+    factions.extend(creatures(&bystanders, json!({ "name": "Townsfolk" })));
+    add("Faction", factions);
+    let mut move_modes = creatures(&monsters, json!({ "mode": "Static" }));
+    move_modes.extend(creatures(&bystanders, json!({ "mode": "Random" })));
+    add("MoveMode", move_modes);
+    let npcs: HashSet<u64> = monsters.union(&bystanders).copied().collect();
+    let mut initiatives = creatures(&players, json!({ "current": 0 }));
+    initiatives.extend(creatures(&npcs, json!({ "current": 2 })));
+    add("Initiative", initiatives);
+    add(
+        "Awareness",
+        creatures(&npcs, json!({ "search_turns": 5, "alert_radius": null })),
+    );
+
+    // Confusion was both what a scroll inflicts and what a confused creature is suffering
+    let (inflicts, suffering): (Vec<_>, Vec<_>) = section_entries(&legacy, "Confusion")
+        .into_iter()
+        .partition(|(id, _)| items.contains(id));
+    add(
+        "InflictsStatus",
+        inflicts
+            .into_iter()
+            .map(|(id, confusion)| {
+                let status = json!({
+                    "kind": "Confused",
+                    "turns": confusion["turns"],
+                    "n_dice": 0,
+                    "die_type": 0,
+                    "bonus": 0,
+                });
+                (id, json!({ "statuses": [status] }))
+            })
+            .collect(),
+    );
+    add(
+        "StatusEffects",
+        suffering
+            .into_iter()
+            .map(|(id, confusion)| {
+                let status =
+                    json!({ "kind": "Confused", "turns": confusion["turns"], "amount": 0 });
+                (id, json!({ "effects": [status] }))
+            })
+            .collect(),
+    );
+
+    // Flat equipment bonuses become a weapon's damage bonus and armor's armor class
+    add(
+        "MeleeWeapon",
+        section_entries(&legacy, "MeleePowerBonus")
+            .into_iter()
+            .map(|(id, bonus)| {
+                let weapon = json!({
+                    "attribute": "Might",
+                    "damage_n_dice": 1,
+                    "damage_die_type": 4,
+                    "damage_bonus": bonus["power"],
+                    "hit_bonus": 0,
+                });
+                (id, weapon)
+            })
+            .collect(),
+    );
+    add(
+        "Wearable",
+        section_entries(&legacy, "DefenseBonus")
+            .into_iter()
+            .map(|(id, bonus)| {
+                let armor_class = bonus["defense"].as_f64().unwrap_or(0.0);
+                (id, json!({ "armor_class": armor_class, "weight_lbs": 0.0 }))
+            })
+            .collect(),
+    );
+
+    // There was only ever the one level, so the dungeon record is rebuilt around it
+    let (_, helper) = section_entries(&legacy, "SerializationHelper")
+        .into_iter()
+        .next()
+        .ok_or("The save has no map")?;
+    let depth = helper["map"]["depth"]
+        .as_i64()
+        .ok_or("The saved map has no depth")?;
+    let next_id = LEGACY_SECTIONS
+        .iter()
+        .flat_map(|name| section_entries(&legacy, name))
+        .map(|(id, _)| id + 1)
+        .max()
+        .unwrap_or(0);
+    let mut maps = Map::new();
+    maps.insert(depth.to_string(), helper["map"].clone());
+    add(
+        "DMSerializationHelper",
+        vec![(
+            next_id,
+            json!({ "map": { "maps": maps, "seed": 0, "turns": 0 } }),
+        )],
+    );
+
+    Ok(json!({ "version": 2, "components": components }))
+}
+
+/// Reads a save of any known version and brings it up to date.
+fn parse_save(data: &str) -> Result<SaveFile, String> {
+    let values = serde_json::Deserializer::from_str(data)
+        .into_iter::<Value>()
+        .collect::<Result<Vec<Value>, _>>()
+        .map_err(|e| format!("The save file is corrupt ({})", e))?;
+
+    let (mut version, mut save) = match values.as_slice() {
+        [Value::Object(header)] if header.contains_key("version") => {
+            let version = header["version"]
+                .as_u64()
+                .filter(|version| *version > 0)
+                .ok_or("The save file has a bad version number")?;
+            (version as u32, values[0].clone())
+        }
+        _ => (1, Value::Array(values)),
+    };
+
+    if version > SAVE_VERSION {
+        return Err(format!(
+            "The save is from a newer version of the game (format {}, this game reads up to {})",
+            version, SAVE_VERSION
+        ));
+    }
+    while version < SAVE_VERSION {
+        let migrate = MIGRATIONS[version as usize - 1];
+        save = migrate(save)
+            .map_err(|e| format!("Couldn't upgrade save from format {}: {}", version, e))?;
+        version += 1;
+    }
+
+    serde_json::from_value(save).map_err(|e| format!("The save file is corrupt ({})", e))
 }
 
 macro_rules! deserialize_individually {
-    ($ecs:expr, $de:expr, $data:expr, $( $type:ty),*) => {
+    ($ecs:expr, $sections:expr, $data:expr, $( $type:ty),*) => {
         $(
-        DeserializeComponents::<NoError, _>::deserialize(
-            &mut ( &mut $ecs.write_storage::<$type>(), ),
-            &$data.0, // entities
-            &mut $data.1, // marker
-            &mut $data.2, // allocater
-            &mut $de,
-        )
-        .unwrap();
+        let name = stringify!($type);
+        // Sections missing from older saves just leave the storage empty
+        if let Some(section) = $sections.get(name) {
+            DeserializeComponents::<NoError, _>::deserialize(
+                &mut ( &mut $ecs.write_storage::<$type>(), ),
+                &$data.0, // entities
+                &mut $data.1, // marker
+                &mut $data.2, // allocater
+                section,
+            )
+            .map_err(|e| format!("The {} section of the save is damaged ({})", name, e))?;
+        }
         )*
     };
 }
 
-pub fn load_game(ecs: &mut World) {
+/// Replaces every entity in the world with the ones in the save.
+fn deserialize_world(ecs: &mut World, save: &SaveFile) -> Result<(), String> {
     {
         // Delete everything
         let mut to_delete = Vec::new();
@@ -124,9 +650,6 @@ pub fn load_game(ecs: &mut World) {
         }
     }
 
-    let data = fs::read_to_string("./savegame.json").unwrap();
-    let mut de = serde_json::Deserializer::from_str(&data);
-
     {
         let mut d = (
             &mut ecs.entities(),
@@ -134,55 +657,67 @@ pub fn load_game(ecs: &mut World) {
             &mut ecs.write_resource::<SimpleMarkerAllocator<SerializeMe>>(),
         );
 
-        deserialize_individually!(
-            ecs,
-            de,
-            d,
-            Position,
-            Renderable,
-            Player,
-            Viewshed,
-            Monster,
-            Name,
-            BlocksTile,
-            CombatStats,
-            SufferDamage,
-            WantsToMelee,
-            Item,
-            Consumable,
-            Ranged,
-            InflictsDamage,
-            AreaOfEffect,
-            Confusion,
-            ProvidesHealing,
-            InBackpack,
-            WantsToPickupItem,
-            WantsToUseItem,
-            WantsToDropItem,
-            SerializationHelper,
-            Equippable,
-            Equipped,
-            MeleePowerBonus,
-            DefenseBonus,
-            WantsToRemoveItem,
-            ParticleLifetime,
-            HungerClock,
-            ProvidesFood,
-            MagicMapper,
-            Hidden,
-            EntryTrigger,
-            EntityMoved,
-            SingleActivation,
-            BlocksVisibility,
-            Door,
-            Bystander
-        );
+        saved_components!(deserialize_individually, ecs, save.components, d);
+    }
+    Ok(())
+}
+
+/// Loads the save into a throwaway world, to find out whether it's damaged or missing anything
+/// a game needs before the real world is emptied to make room for it.
+fn check_save(save: &SaveFile) -> Result<(), String> {
+    let mut scratch = World::new();
+    register_components(&mut scratch);
+    deserialize_world(&mut scratch, save)?;
+
+    if scratch.read_storage::<SerializationHelper>().is_empty() {
+        return Err("The save has no map".to_string());
+    }
+    if scratch.read_storage::<DMSerializationHelper>().is_empty() {
+        return Err("The save has no dungeon record".to_string());
     }
+    let players = scratch.read_storage::<Player>();
+    let positions = scratch.read_storage::<Position>();
+    if (&players, &positions).join().next().is_none() {
+        return Err("The save has no player".to_string());
+    }
+
+    for name in save.components.keys() {
+        if !SAVED_COMPONENTS.contains(&name.as_str()) {
+            rltk::console::log(format!(
+                "WARNING - ignoring unknown save section [{}]",
+                name
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Replaces the world with the saved game, or explains why it couldn't. A save that can't
+/// be read, is damaged or is missing part of the game leaves the world untouched.
+pub fn load_game(ecs: &mut World, slot: usize) -> Result<(), String> {
+    load_game_from(&*default_storage(), ecs, slot)
+}
+
+fn load_game_from(storage: &dyn SaveStorage, ecs: &mut World, slot: usize) -> Result<(), String> {
+    let data = storage
+        .read(&slot_name(slot))?
+        .ok_or("There's no save in that slot")?;
+    load_from_str(ecs, &data)
+}
+
+/// Loads a game from save data that has already been read in.
+fn load_from_str(ecs: &mut World, data: &str) -> Result<(), String> {
+    let save = parse_save(data)?;
+    check_save(&save)?;
+    super::effects::clear_effects();
+    deserialize_world(ecs, &save)?;
 
     let mut deleteme: Option<Entity> = None;
+    let mut deleteme2: Option<Entity> = None;
     {
         let entities = ecs.entities();
         let helper = ecs.read_storage::<SerializationHelper>();
+        let helper2 = ecs.read_storage::<DMSerializationHelper>();
         let player = ecs.read_storage::<Player>();
         let position = ecs.read_storage::<Position>();
         for (e, h) in (&entities, &helper).join() {
@@ -191,6 +726,11 @@ pub fn load_game(ecs: &mut World) {
             worldmap.tile_content = vec![Vec::new(); (worldmap.height * worldmap.width) as usize];
             deleteme = Some(e);
         }
+        for (e, h) in (&entities, &helper2).join() {
+            let mut dungeonmaster = ecs.write_resource::<super::map::MasterDungeonMap>();
+            *dungeonmaster = h.map.clone();
+            deleteme2 = Some(e);
+        }
         for (e, _p, pos) in (&entities, &player, &position).join() {
             let mut ppos = ecs.write_resource::<rltk::Point>();
             *ppos = rltk::Point::new(pos.x, pos.y);
@@ -200,10 +740,502 @@ pub fn load_game(ecs: &mut World) {
     }
     ecs.delete_entity(deleteme.unwrap())
         .expect("Unable to delete helper");
+    ecs.delete_entity(deleteme2.unwrap())
+        .expect("Unable to delete helper");
+    Ok(())
 }
 
-pub fn delete_save() {
-    if Path::new("./savegame.json").exists() {
-        std::fs::remove_file("./savegame.json").expect("Unable to delete file");
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::{Map, MasterDungeonMap};
+    use crate::map_builders::level_builder;
+    use crate::raws::{self, spawn_named_entity, spawn_named_item, SpawnType, RAWS};
+    use crate::save_storage::MemoryStorage;
+    use crate::spawner;
+    use rltk::Point;
+
+    fn new_world() -> World {
+        let mut ecs = World::new();
+        register_components(&mut ecs);
+        ecs.insert(Map::new(1, 8, 8));
+        ecs.insert(MasterDungeonMap::new(1));
+        ecs.insert(Point::new(0, 0));
+        let placeholder = ecs.create_entity().build();
+        ecs.insert(placeholder);
+        ecs
+    }
+
+    /// Sets up a game the way `main` does, but without a window: a player with some gear,
+    /// standing on a freshly built level full of spawned entities.
+    fn new_game(depth: i32, seed: u64) -> World {
+        raws::load_raws();
+        let mut ecs = new_world();
+        ecs.insert(MasterDungeonMap::new(seed));
+        let player = spawner::player(&mut ecs, 0, 0);
+        ecs.insert(player);
+
+        let mut builder = level_builder(depth, seed, 80, 50);
+        builder.build_map();
+        let start = builder.build_data.starting_position.clone().unwrap();
+        ecs.insert(builder.build_data.map.clone());
+        builder.spawn_entities(&mut ecs);
+        ecs.write_storage::<Position>()
+            .insert(
+                player,
+                Position {
+                    x: start.x,
+                    y: start.y,
+                },
+            )
+            .unwrap();
+        ecs.insert(Point::new(start.x, start.y));
+
+        for name in ["Dagger", "Health Potion"].iter() {
+            spawn_named_item(
+                &RAWS.lock().unwrap(),
+                ecs.create_entity().marked::<SimpleMarker<SerializeMe>>(),
+                name,
+                SpawnType::Carried { by: player },
+            )
+            .unwrap();
+        }
+        let dagger = {
+            let names = ecs.read_storage::<Name>();
+            (&ecs.entities(), &names)
+                .join()
+                .find(|(_, n)| n.name == "Dagger")
+                .unwrap()
+                .0
+        };
+        ecs.write_storage::<InBackpack>().remove(dagger);
+        ecs.write_storage::<Equipped>()
+            .insert(
+                dagger,
+                Equipped {
+                    owner: player,
+                    slot: EquipmentSlot::Melee,
+                },
+            )
+            .unwrap();
+        ecs
+    }
+
+    macro_rules! storage_counts {
+        ($ecs:expr, $( $type:ty),*) => {
+            vec![ $( (stringify!($type), $ecs.read_storage::<$type>().join().count()) ),* ]
+        };
+    }
+
+    /// Who holds what, by name, so links can be compared across worlds.
+    fn carried_items(ecs: &World) -> Vec<(String, String)> {
+        let names = ecs.read_storage::<Name>();
+        let backpack = ecs.read_storage::<InBackpack>();
+        let equipped = ecs.read_storage::<Equipped>();
+        let mut carried: Vec<(String, String)> = (&names, &backpack)
+            .join()
+            .map(|(item, pack)| {
+                (
+                    item.name.clone(),
+                    names.get(pack.owner).unwrap().name.clone(),
+                )
+            })
+            .chain((&names, &equipped).join().map(|(item, equip)| {
+                (
+                    item.name.clone(),
+                    names.get(equip.owner).unwrap().name.clone(),
+                )
+            }))
+            .collect();
+        carried.sort();
+        carried
+    }
+
+    #[test]
+    fn every_component_is_registered() {
+        let source = include_str!("components.rs");
+        let mut lines = source.lines();
+        while let Some(line) = lines.next() {
+            if !(line.starts_with("#[derive(") && line.contains("Component")) {
+                continue;
+            }
+            let name = lines
+                .find_map(|l| l.strip_prefix("pub struct "))
+                .and_then(|l| l.split(|c: char| !c.is_alphanumeric()).next())
+                .unwrap();
+            assert!(
+                SAVED_COMPONENTS.contains(&name),
+                "{} is a component but isn't in saved_components!",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn every_registered_component_round_trips() {
+        let mut ecs = new_game(1, 7);
+        let player = *ecs.fetch::<Entity>();
+
+        // A level doesn't spawn one of everything, so add what's missing
+        let mut spawned = Vec::new();
+        for name in [
+            "Blink Scroll",
+            "Fireball Scroll",
+            "Confusion Scroll",
+            "Magic Mapping Scroll",
+            "Rations",
+            "Leather Armor",
+            "Shortbow",
+            "Quiver of Arrows",
+            "Bear Trap",
+        ]
+        .iter()
+        {
+            let entity = spawn_named_entity(
+                &RAWS.lock().unwrap(),
+                ecs.create_entity().marked::<SimpleMarker<SerializeMe>>(),
+                name,
+                SpawnType::AtPosition { x: 1, y: 1 },
+            );
+            spawned.push(entity.unwrap());
+        }
+        let item = spawned[0];
+        ecs.create_entity()
+            .with(WantsToMelee { target: player })
+            .with(MyTurn {})
+            .with(WantsToApproach { idx: 3 })
+            .with(WantsToFlee {
+                indices: vec![1, 2],
+            })
+            .with(Chasing {
+                target: player,
+                last_seen: 4,
+            })
+            .with(Searching {
+                center: 5,
+                turns: 2,
+                destination: Some(6),
+            })
+            .with(WantsToPickupItem {
+                collected_by: player,
+                item,
+            })
+            .with(WantsToUseItem {
+                item,
+                target: Some(Point::new(3, 4)),
+            })
+            .with(WantsToDropItem { item })
+            .with(WantsToShoot { target: player })
+            .with(WantsToRemoveItem { item })
+            .with(ParticleLifetime { lifetime_ms: 200.0 })
+            .with(StatusEffects {
+                effects: vec![Status {
+                    kind: StatusKind::Poisoned,
+                    turns: 3,
+                    amount: 2,
+                }],
+            })
+            .with(EntityMoved {})
+            .with(OtherLevelPosition {
+                x: 1,
+                y: 2,
+                depth: 3,
+            })
+            .marked::<SimpleMarker<SerializeMe>>()
+            .build();
+
+        let save = serialize_world(&mut ecs);
+        assert_eq!(save.components.len(), SAVED_COMPONENTS.len());
+        let mut loaded = new_world();
+        load_from_str(&mut loaded, &serde_json::to_string(&save).unwrap()).unwrap();
+        let resaved = serialize_world(&mut loaded);
+
+        // The helpers hold maps, whose sets come out in any order, so
+        // `game_survives_a_save_and_load` checks those field by field instead
+        for name in SAVED_COMPONENTS
+            .iter()
+            .filter(|name| !name.ends_with("SerializationHelper"))
+        {
+            let mut before = section_entries(&save.components, name);
+            let mut after = section_entries(&resaved.components, name);
+            assert!(!before.is_empty(), "nothing had a {} to save", name);
+            before.sort_by_key(|(id, _)| *id);
+            after.sort_by_key(|(id, _)| *id);
+            assert_eq!(before, after, "{} changed in a save and load", name);
+        }
+    }
+
+    #[test]
+    fn game_survives_a_save_and_load() {
+        for (depth, seed) in [(1, 7), (3, 42)].iter() {
+            let mut ecs = new_game(*depth, *seed);
+            let data = save_to_string(&mut ecs);
+
+            let mut loaded = new_world();
+            load_from_str(&mut loaded, &data).unwrap();
+
+            // Every component on every entity came back, and the level had plenty of them
+            assert!(loaded.read_storage::<Name>().join().count() > 10);
+            assert_eq!(
+                saved_components!(storage_counts, loaded),
+                saved_components!(storage_counts, ecs)
+            );
+            assert_eq!(
+                loaded
+                    .read_storage::<SimpleMarker<SerializeMe>>()
+                    .join()
+                    .count(),
+                ecs.read_storage::<SimpleMarker<SerializeMe>>()
+                    .join()
+                    .count()
+            );
+
+            // The map is the one we left
+            {
+                let before = ecs.fetch::<Map>();
+                let after = loaded.fetch::<Map>();
+                assert_eq!(after.depth, before.depth);
+                assert_eq!(after.width, before.width);
+                assert_eq!(after.height, before.height);
+                assert!(after.tiles == before.tiles);
+                assert_eq!(after.revealed_tiles, before.revealed_tiles);
+                assert_eq!(after.blocked, before.blocked);
+                assert_eq!(after.bloodstains, before.bloodstains);
+                assert_eq!(after.view_blocked, before.view_blocked);
+                assert_eq!(loaded.fetch::<MasterDungeonMap>().seed, *seed);
+            }
+
+            // Carried and equipped items still point at their owners
+            let carried = carried_items(&loaded);
+            assert!(carried.contains(&("Dagger".to_string(), "Player".to_string())));
+            assert!(carried.contains(&("Health Potion".to_string(), "Player".to_string())));
+            assert_eq!(carried, carried_items(&ecs));
+
+            // The player resource points at the loaded player, where they were standing
+            let player = *loaded.fetch::<Entity>();
+            assert!(loaded.read_storage::<Player>().get(player).is_some());
+            let pos = loaded
+                .read_storage::<Position>()
+                .get(player)
+                .unwrap()
+                .clone();
+            let original = *ecs.fetch::<Entity>();
+            let original_pos = ecs
+                .read_storage::<Position>()
+                .get(original)
+                .unwrap()
+                .clone();
+            assert_eq!((pos.x, pos.y), (original_pos.x, original_pos.y));
+            assert_eq!(*loaded.fetch::<Point>(), Point::new(pos.x, pos.y));
+            let names = loaded.read_storage::<Name>();
+            let equipped = loaded.read_storage::<Equipped>();
+            let (_, dagger) = (&names, &equipped)
+                .join()
+                .find(|(n, _)| n.name == "Dagger")
+                .unwrap();
+            assert_eq!(dagger.owner, player);
+        }
+    }
+
+    #[test]
+    fn saves_go_through_the_storage_backend() {
+        let mut storage = MemoryStorage::default();
+        assert!(slots_in(&storage)
+            .iter()
+            .all(|slot| matches!(slot, SaveSlot::Empty)));
+
+        let mut ecs = new_game(1, 99);
+        ecs.write_resource::<MasterDungeonMap>().turns = 12;
+        save_game_to(&mut storage, &mut ecs, 1).unwrap();
+
+        let slots = slots_in(&storage);
+        assert!(matches!(slots[0], SaveSlot::Empty));
+        match &slots[1] {
+            SaveSlot::Saved(meta) => {
+                assert_eq!((meta.level, meta.depth, meta.turns), (1, 1, 12));
+                assert_eq!(meta.seed, 99);
+            }
+            _ => panic!("slot 2 should hold the save"),
+        }
+
+        let mut loaded = new_world();
+        load_game_from(&storage, &mut loaded, 1).unwrap();
+        assert_eq!(loaded.fetch::<MasterDungeonMap>().turns, 12);
+        assert!(load_game_from(&storage, &mut new_world(), 0).is_err());
+    }
+
+    /// Writes a save the way the original game did: every legacy section back to back, with
+    /// the given entities filled in and the rest empty.
+    fn legacy_save(entities: &[(u64, &[(&str, Value)])]) -> String {
+        LEGACY_SECTIONS
+            .iter()
+            .map(|name| {
+                let entries: Vec<(u64, Value)> = entities
+                    .iter()
+                    .filter_map(|(id, components)| {
+                        let (_, data) = components.iter().find(|(n, _)| n == name)?;
+                        Some((*id, data.clone()))
+                    })
+                    .collect();
+                section_from_entries(entries).to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn original_saves_are_migrated() {
+        let map = serde_json::to_value(Map::new(1, 8, 8)).unwrap();
+        let position = json!({ "x": 2, "y": 3 });
+        let viewshed = json!({ "visible_tiles": [], "range": 8, "dirty": true });
+        let data = legacy_save(&[
+            (
+                0,
+                &[
+                    ("Position", position.clone()),
+                    ("Player", json!({})),
+                    ("Viewshed", viewshed.clone()),
+                    ("Name", json!({ "name": "Player" })),
+                    (
+                        "CombatStats",
+                        json!({ "max_hp": 30, "hp": 21, "defense": 2, "power": 5 }),
+                    ),
+                    ("HungerClock", json!({ "state": "Hungry", "duration": 20 })),
+                ],
+            ),
+            (
+                1,
+                &[
+                    ("Position", json!({ "x": 5, "y": 5 })),
+                    ("Viewshed", viewshed),
+                    ("Monster", json!({})),
+                    ("Name", json!({ "name": "Orc" })),
+                    ("BlocksTile", json!({})),
+                    (
+                        "CombatStats",
+                        json!({ "max_hp": 16, "hp": 16, "defense": 1, "power": 4 }),
+                    ),
+                    ("Confusion", json!({ "turns": 3 })),
+                ],
+            ),
+            (
+                2,
+                &[
+                    ("Name", json!({ "name": "Longsword" })),
+                    ("Item", json!({})),
+                    ("Equippable", json!({ "slot": "Melee" })),
+                    ("Equipped", json!({ "owner": [0], "slot": "Melee" })),
+                    ("MeleePowerBonus", json!({ "power": 4 })),
+                ],
+            ),
+            (
+                3,
+                &[
+                    ("Name", json!({ "name": "Confusion Scroll" })),
+                    ("Item", json!({})),
+                    ("Consumable", json!({})),
+                    ("Ranged", json!({ "range": 6 })),
+                    ("Confusion", json!({ "turns": 4 })),
+                    ("InBackpack", json!({ "owner": [0] })),
+                ],
+            ),
+            (4, &[("SerializationHelper", json!({ "map": map }))]),
+        ]);
+
+        let mut ecs = new_world();
+        load_from_str(&mut ecs, &data).unwrap();
+
+        let player = *ecs.fetch::<Entity>();
+        assert_eq!(*ecs.fetch::<Point>(), Point::new(2, 3));
+        let pools = ecs.read_storage::<Pools>();
+        let hit_points = &pools.get(player).unwrap().hit_points;
+        assert_eq!((hit_points.current, hit_points.max), (21, 30));
+        assert!(ecs.read_storage::<Attributes>().contains(player));
+        assert_eq!(
+            ecs.read_storage::<Faction>().get(player).unwrap().name,
+            "Player"
+        );
+        assert!(ecs.fetch::<MasterDungeonMap>().get_map(1).is_some());
+
+        let names = ecs.read_storage::<Name>();
+        let named = |name: &str| {
+            (&ecs.entities(), &names)
+                .join()
+                .find(|(_, n)| n.name == name)
+                .map(|(entity, _)| entity)
+                .unwrap()
+        };
+        let orc = named("Orc");
+        assert_eq!(
+            ecs.read_storage::<Faction>().get(orc).unwrap().name,
+            "Mindless"
+        );
+        assert!(ecs.read_storage::<Initiative>().contains(orc));
+        assert!(ecs.read_storage::<StatusEffects>().contains(orc));
+
+        let sword = named("Longsword");
+        let weapons = ecs.read_storage::<MeleeWeapon>();
+        assert_eq!(weapons.get(sword).unwrap().damage_bonus, 4);
+        assert_eq!(
+            ecs.read_storage::<Equipped>().get(sword).unwrap().owner,
+            player
+        );
+
+        let scroll = named("Confusion Scroll");
+        assert!(ecs.read_storage::<InflictsStatus>().contains(scroll));
+        assert_eq!(
+            ecs.read_storage::<InBackpack>().get(scroll).unwrap().owner,
+            player
+        );
+    }
+
+    #[test]
+    fn unknown_versions_are_rejected() {
+        assert!(parse_save(r#"{"version":0,"components":{}}"#).is_err());
+        assert!(parse_save(r#"{"version":99,"components":{}}"#).is_err());
+    }
+
+    #[test]
+    fn old_single_file_saves_are_moved_into_a_slot() {
+        let path = std::env::temp_dir().join(format!("legacy-save-{}.json", std::process::id()));
+        let map = serde_json::to_value(Map::new(1, 8, 8)).unwrap();
+        let data = legacy_save(&[
+            (
+                0,
+                &[
+                    ("Position", json!({ "x": 1, "y": 1 })),
+                    ("Player", json!({})),
+                ],
+            ),
+            (1, &[("SerializationHelper", json!({ "map": map }))]),
+        ]);
+        std::fs::write(&path, &data).unwrap();
+
+        let mut storage = MemoryStorage::default();
+        storage.write(&slot_name(0), "taken").unwrap();
+        move_legacy_save(&mut storage, &path);
+
+        assert!(!path.exists());
+        assert!(matches!(slots_in(&storage)[1], SaveSlot::Unknown));
+        load_game_from(&storage, &mut new_world(), 1).unwrap();
+    }
+
+    #[test]
+    fn damaged_saves_leave_the_game_alone() {
+        let mut ecs = new_game(1, 5);
+        let mut damaged = serialize_world(&mut ecs);
+        damaged.components.insert(
+            "Pools".to_string(),
+            json!([{ "components": [7], "marker": [0] }]),
+        );
+        let mut incomplete = serialize_world(&mut ecs);
+        incomplete.components.remove("DMSerializationHelper");
+
+        let names = |ecs: &World| ecs.read_storage::<Name>().join().count();
+        let before = names(&ecs);
+        let damaged = load_from_str(&mut ecs, &serde_json::to_string(&damaged).unwrap());
+        assert!(damaged.unwrap_err().contains("Pools"));
+        let incomplete = load_from_str(&mut ecs, &serde_json::to_string(&incomplete).unwrap());
+        assert_eq!(incomplete.unwrap_err(), "The save has no dungeon record");
+        assert_eq!(names(&ecs), before);
     }
 }
@@ -1,6 +1,7 @@
 use super::{
-    random_table::RandomTable, raws::*, CombatStats, HungerClock, HungerState, Map, Name, Player,
-    Position, Rect, Renderable, SerializeMe, TileType, Viewshed,
+    gamesystem::player_hp_at_level, random_table::RandomTable, raws::*, Attribute, Attributes,
+    Faction, HungerClock, HungerState, Initiative, Map, Name, Player, Pool, Pools, Position, Rect,
+    Renderable, SerializeMe, Skill, Skills, TileType, Viewshed,
 };
 use rltk::{RandomNumberGenerator, RGB};
 use specs::prelude::*;
@@ -9,6 +10,12 @@ use std::collections::HashMap;
 
 /// Spawns the player and returns his/her entity object.
 pub fn player(ecs: &mut World, player_x: i32, player_y: i32) -> Entity {
+    let mut skills = HashMap::new();
+    skills.insert(Skill::Melee, 1);
+    skills.insert(Skill::Defense, 1);
+    skills.insert(Skill::Magic, 1);
+    skills.insert(Skill::Ranged, 1);
+
     ecs.create_entity()
         .with(Position {
             x: player_x,
@@ -29,16 +36,30 @@ pub fn player(ecs: &mut World, player_x: i32, player_y: i32) -> Entity {
         .with(Name {
             name: "Player".to_string(),
         })
-        .with(CombatStats {
-            max_hp: 30,
-            hp: 30,
-            defense: 2,
-            power: 5,
+        .with(Attributes {
+            might: Attribute::new(11),
+            fitness: Attribute::new(11),
+            quickness: Attribute::new(11),
+            intelligence: Attribute::new(11),
+        })
+        .with(Skills { skills })
+        .with(Pools {
+            hit_points: Pool {
+                current: player_hp_at_level(11, 1),
+                max: player_hp_at_level(11, 1),
+            },
+            xp: 0,
+            level: 1,
+            gold: 50.0,
         })
         .with(HungerClock {
             state: HungerState::WellFed,
             duration: 20,
         })
+        .with(Initiative { current: 0 })
+        .with(Faction {
+            name: "Player".to_string(),
+        })
         .marked::<SimpleMarker<SerializeMe>>()
         .build()
 }
@@ -104,8 +125,10 @@ pub fn spawn_region(
         }
     }
 
-    // Actually spawn the monsters
-    for spawn in spawn_points.iter() {
+    // Actually spawn the monsters, in tile order so seeded runs produce identical lists
+    let mut spawns: Vec<(&usize, &String)> = spawn_points.iter().collect();
+    spawns.sort_by_key(|spawn| *spawn.0);
+    for spawn in spawns.iter() {
         spawn_list.push((*spawn.0, spawn.1.to_string()));
     }
 }
@@ -120,7 +143,7 @@ pub fn spawn_entity(ecs: &mut World, spawn: &(&usize, &String)) {
 
     let spawn_result = spawn_named_entity(
         &RAWS.lock().unwrap(),
-        ecs.create_entity(),
+        ecs.create_entity().marked::<SimpleMarker<SerializeMe>>(),
         &spawn.1,
         SpawnType::AtPosition { x, y },
     );
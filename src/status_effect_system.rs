@@ -0,0 +1,104 @@
+use super::{
+    effects::{add_effect, EffectType, Targets},
+    gamelog::GameLog,
+    MyTurn, StatusEffects, StatusKind, Viewshed,
+};
+use specs::prelude::*;
+
+/// Counts down status effects, queueing poison damage and regeneration as it goes. Like hunger,
+/// statuses only tick when their bearer gets a turn, so fast creatures shake them off sooner.
+pub struct StatusEffectSystem {}
+
+impl<'a> System<'a> for StatusEffectSystem {
+    #[allow(clippy::type_complexity)]
+    type SystemData = (
+        Entities<'a>,
+        WriteStorage<'a, StatusEffects>,
+        ReadExpect<'a, Entity>, // The player
+        WriteStorage<'a, Viewshed>,
+        WriteExpect<'a, GameLog>,
+        ReadStorage<'a, MyTurn>,
+    );
+
+    fn run(&mut self, data: Self::SystemData) {
+        let (entities, mut status_effects, player_entity, mut viewsheds, mut log, turns) = data;
+
+        let mut cured: Vec<Entity> = Vec::new();
+        for (entity, statuses, _myturn) in (&entities, &mut status_effects, &turns).join() {
+            let is_player = entity == *player_entity;
+            for status in statuses.effects.iter_mut() {
+                // Spread what's left of the poison or healing evenly over the remaining turns
+                let portion = if status.turns > 0 {
+                    (status.amount + status.turns - 1) / status.turns
+                } else {
+                    status.amount
+                };
+                match status.kind {
+                    StatusKind::Poisoned if portion > 0 => {
+                        add_effect(
+                            None,
+                            EffectType::Damage { amount: portion },
+                            Targets::Single { target: entity },
+                        );
+                        add_effect(
+                            None,
+                            EffectType::Particle {
+                                glyph: rltk::to_cp437('♣'),
+                                fg: rltk::RGB::named(rltk::GREEN),
+                                bg: rltk::RGB::named(rltk::BLACK),
+                                lifespan: 200.0,
+                            },
+                            Targets::Single { target: entity },
+                        );
+                        if is_player {
+                            log.entries
+                                .push(format!("You suffer {} hp from poison.", portion));
+                        }
+                    }
+                    StatusKind::Regenerating if portion > 0 => {
+                        add_effect(
+                            None,
+                            EffectType::Healing { amount: portion },
+                            Targets::Single { target: entity },
+                        );
+                    }
+                    _ => {}
+                }
+                status.amount -= portion;
+                status.turns -= 1;
+            }
+
+            // Let the player know what has worn off
+            if is_player {
+                let mut worn_off: Vec<StatusKind> = Vec::new();
+                for status in statuses.effects.iter().filter(|s| s.turns < 1) {
+                    let still_active = statuses
+                        .effects
+                        .iter()
+                        .any(|s| s.kind == status.kind && s.turns > 0);
+                    if !still_active && !worn_off.contains(&status.kind) {
+                        worn_off.push(status.kind);
+                    }
+                }
+                for kind in worn_off {
+                    log.entries
+                        .push(format!("You are no longer {}.", kind.name().to_lowercase()));
+                }
+            }
+            statuses.effects.retain(|s| s.turns > 0);
+
+            // Blindness changes how far you can see, so look again
+            if let Some(vs) = viewsheds.get_mut(entity) {
+                vs.dirty = true;
+            }
+
+            if statuses.effects.is_empty() {
+                cured.push(entity);
+            }
+        }
+
+        for entity in cured {
+            status_effects.remove(entity);
+        }
+    }
+}
@@ -1,4 +1,7 @@
-use super::{gamelog::GameLog, BlocksVisibility, Hidden, Map, Name, Player, Position, Viewshed};
+use super::{
+    gamelog::GameLog, BlocksVisibility, Hidden, Map, Name, Player, Position, StatusEffects,
+    StatusKind, Viewshed,
+};
 use rltk::{field_of_view, Point};
 use specs::prelude::*;
 
@@ -17,6 +20,7 @@ impl<'a> System<'a> for VisibilitySystem {
         WriteExpect<'a, GameLog>,
         ReadStorage<'a, Name>,
         ReadStorage<'a, BlocksVisibility>,
+        ReadStorage<'a, StatusEffects>,
     );
 
     fn run(&mut self, data: Self::SystemData) {
@@ -31,6 +35,7 @@ impl<'a> System<'a> for VisibilitySystem {
             mut log,
             names,
             blocks_visibility,
+            status_effects,
         ) = data;
 
         map.view_blocked.clear();
@@ -42,8 +47,12 @@ impl<'a> System<'a> for VisibilitySystem {
         for (ent, viewshed, pos) in (&entities, &mut viewshed, &pos).join() {
             if viewshed.dirty {
                 viewshed.dirty = false;
-                viewshed.visible_tiles =
-                    field_of_view(Point::new(pos.x, pos.y), viewshed.range, &*map);
+                // The blind can only feel their way around
+                let blinded = status_effects
+                    .get(ent)
+                    .is_some_and(|s| s.has(StatusKind::Blinded));
+                let range = if blinded { 1 } else { viewshed.range };
+                viewshed.visible_tiles = field_of_view(Point::new(pos.x, pos.y), range, &*map);
                 viewshed
                     .visible_tiles
                     .retain(|p| p.x >= 0 && p.x < map.width && p.y >= 0 && p.y < map.height);